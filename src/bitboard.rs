@@ -0,0 +1,187 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+use crate::board::{Pos, MAX_GRID_SIZE};
+
+/// Bits per row: one guard column past the widest board keeps shifted lines from wrapping.
+pub const STRIDE: usize = MAX_GRID_SIZE + 1;
+pub const NB_BITS: usize = MAX_GRID_SIZE * STRIDE;
+const NB_WORDS: usize = NB_BITS.div_ceil(64);
+
+/// Index offsets of the four line directions: horizontal, vertical, diagonal, anti-diagonal.
+pub const LINE_SHIFT: [usize; 4] = [1, STRIDE, STRIDE + 1, STRIDE - 1];
+
+/// Set of cells, one bit per cell at `y * STRIDE + x`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct BitSet([u64; NB_WORDS]);
+
+/// Cells of a `size` x `size` board, for every supported size.
+static INSIDE_MASK: [BitSet; MAX_GRID_SIZE + 1] = inside_masks();
+
+const fn inside_masks() -> [BitSet; MAX_GRID_SIZE + 1] {
+    let mut masks = [BitSet([0; NB_WORDS]); MAX_GRID_SIZE + 1];
+    let mut size = 0;
+    while size <= MAX_GRID_SIZE {
+        let mut y = 0;
+        while y < size {
+            let mut x = 0;
+            while x < size {
+                let i = y * STRIDE + x;
+                masks[size].0[i / 64] |= 1 << (i % 64);
+                x += 1;
+            }
+            y += 1;
+        }
+        size += 1;
+    }
+    masks
+}
+
+impl BitSet {
+    pub const fn new() -> Self {
+        BitSet([0; NB_WORDS])
+    }
+
+    pub fn inside(size: usize) -> Self {
+        INSIDE_MASK[size]
+    }
+
+    pub fn index(p: Pos) -> usize {
+        (p.y as usize) * STRIDE + (p.x as usize)
+    }
+
+    pub fn pos(i: usize) -> Pos {
+        Pos { x: (i % STRIDE) as i16, y: (i / STRIDE) as i16 }
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn insert(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn remove(&mut self, i: usize) {
+        self.0[i / 64] &= !(1 << (i % 64));
+    }
+
+    pub fn contains(&self, p: Pos) -> bool {
+        self.get(BitSet::index(p))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|w| *w == 0)
+    }
+
+    pub fn count(&self) -> u32 {
+        self.0.iter().map(|w| w.count_ones()).sum()
+    }
+
+    pub fn and_not(&self, other: &BitSet) -> BitSet {
+        let mut todo = *self;
+        for i in 0..NB_WORDS {
+            todo.0[i] &= !other.0[i];
+        }
+        todo
+    }
+
+    /// Moves every cell `k` indices forward, `0 < k < 64`.
+    pub fn shl(&self, k: usize) -> BitSet {
+        let mut todo = BitSet::new();
+        for i in 0..NB_WORDS {
+            todo.0[i] = self.0[i] << k;
+            if i > 0 {
+                todo.0[i] |= self.0[i - 1] >> (64 - k);
+            }
+        }
+        todo
+    }
+
+    /// Moves every cell `k` indices backward, `0 < k < 64`.
+    pub fn shr(&self, k: usize) -> BitSet {
+        let mut todo = BitSet::new();
+        for i in 0..NB_WORDS {
+            todo.0[i] = self.0[i] >> k;
+            if i + 1 < NB_WORDS {
+                todo.0[i] |= self.0[i + 1] << (64 - k);
+            }
+        }
+        todo
+    }
+
+    /// Cells starting a run of at least `len` members along the line direction `shift`.
+    pub fn runs(&self, shift: usize, len: usize) -> BitSet {
+        let mut todo = *self;
+        for _ in 1..len {
+            todo = todo & todo.shr(shift);
+        }
+        todo
+    }
+
+    /// The set grown by one cell in the eight directions.
+    pub fn dilate(&self) -> BitSet {
+        let mut todo = *self;
+        for &k in LINE_SHIFT.iter() {
+            todo |= self.shl(k) | self.shr(k);
+        }
+        todo
+    }
+
+    pub fn iter(&self) -> BitIter {
+        BitIter { set: *self, word: 0 }
+    }
+}
+
+impl BitAnd for BitSet {
+    type Output = BitSet;
+
+    fn bitand(mut self, rhs: BitSet) -> BitSet {
+        self &= rhs;
+        self
+    }
+}
+
+impl BitAndAssign for BitSet {
+    fn bitand_assign(&mut self, rhs: BitSet) {
+        for i in 0..NB_WORDS {
+            self.0[i] &= rhs.0[i];
+        }
+    }
+}
+
+impl BitOr for BitSet {
+    type Output = BitSet;
+
+    fn bitor(mut self, rhs: BitSet) -> BitSet {
+        self |= rhs;
+        self
+    }
+}
+
+impl BitOrAssign for BitSet {
+    fn bitor_assign(&mut self, rhs: BitSet) {
+        for i in 0..NB_WORDS {
+            self.0[i] |= rhs.0[i];
+        }
+    }
+}
+
+pub struct BitIter {
+    set: BitSet,
+    word: usize,
+}
+
+impl Iterator for BitIter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.word < NB_WORDS {
+            let w = self.set.0[self.word];
+            if w != 0 {
+                self.set.0[self.word] = w & (w - 1);
+                return Some(self.word * 64 + w.trailing_zeros() as usize);
+            }
+            self.word += 1;
+        }
+        None
+    }
+}
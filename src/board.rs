@@ -0,0 +1,528 @@
+use crate::bitboard::{BitSet, LINE_SHIFT};
+use crate::rules::RuleSet;
+use crate::zobrist;
+
+pub const MIN_GRID_SIZE: usize = 5;
+pub const MAX_GRID_SIZE: usize = 20;
+pub const DEFAULT_GRID_SIZE: usize = 19;
+pub const NB_DIR: usize = 8;
+pub const ALL_DIR: [(i16, i16); NB_DIR] = [
+    (0, 1),
+    (0, -1),
+    (1, 0),
+    (-1, 0),
+    (1, 1),
+    (-1, -1),
+    (1, -1),
+    (-1, 1)
+];
+
+pub const NB_CAP_WIN: i16 = 10;
+
+pub const CELL_EMPTY: i8 = 0;
+pub const CELL_WHITE: i8 = 1;
+pub const CELL_BLACK: i8 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Player {
+    White,
+    Black,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Pos {
+    pub x: i16,
+    pub y: i16,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Board {
+    size: usize,
+    black: BitSet,
+    white: BitSet,
+    hash: u64,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new(DEFAULT_GRID_SIZE)
+    }
+}
+
+impl Board {
+    /// Empty `size` x `size` board, `size` must be within `MIN_GRID_SIZE..=MAX_GRID_SIZE`.
+    pub fn new(size: usize) -> Self {
+        assert!((MIN_GRID_SIZE..=MAX_GRID_SIZE).contains(&size), "unsupported board size {}", size);
+        Board {
+            size,
+            black: BitSet::new(),
+            white: BitSet::new(),
+            hash: 0,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn center(&self) -> Pos {
+        Pos { x: (self.size / 2) as i16, y: (self.size / 2) as i16 }
+    }
+
+    pub fn contains(&self, p: Pos) -> bool {
+        p.x >= 0 && (p.x as usize) < self.size && p.y >= 0 && (p.y as usize) < self.size
+    }
+
+    /// Cell content at `p`, which must be on the board.
+    pub fn get(&self, p: Pos) -> i8 {
+        let i = BitSet::index(p);
+        if self.black.get(i) {
+            CELL_BLACK
+        } else if self.white.get(i) {
+            CELL_WHITE
+        } else {
+            CELL_EMPTY
+        }
+    }
+
+    pub fn set(&mut self, p: Pos, c: i8) {
+        let i = BitSet::index(p);
+        let old = self.get(p);
+        if old != CELL_EMPTY {
+            self.hash ^= zobrist::stone_key(i, old);
+        }
+        if c != CELL_EMPTY {
+            self.hash ^= zobrist::stone_key(i, c);
+        }
+
+        self.black.remove(i);
+        self.white.remove(i);
+        match c {
+            CELL_BLACK => self.black.insert(i),
+            CELL_WHITE => self.white.insert(i),
+            _ => (),
+        }
+    }
+
+    /// Zobrist key of the stones on the board.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn stone(&self, p: Pos) -> Option<Player> {
+        match self.get(p) {
+            CELL_BLACK => Some(Player::Black),
+            CELL_WHITE => Some(Player::White),
+            _ => None,
+        }
+    }
+
+    /// Stones of colour `c`.
+    pub fn stones(&self, c: i8) -> BitSet {
+        match c {
+            CELL_BLACK => self.black,
+            CELL_WHITE => self.white,
+            _ => self.empty(),
+        }
+    }
+
+    pub fn occupied(&self) -> BitSet {
+        self.black | self.white
+    }
+
+    pub fn empty(&self) -> BitSet {
+        BitSet::inside(self.size).and_not(&self.occupied())
+    }
+}
+
+pub fn player_to_i8(player: Player) -> i8 {
+    match player {
+        Player::Black => CELL_BLACK,
+        Player::White => CELL_WHITE,
+    }
+}
+
+pub fn player_to_str(player: Player) -> &'static str {
+    match player {
+        Player::Black => "black",
+        Player::White => "white",
+    }
+}
+
+pub fn next_player(player: Player) -> Player {
+    match player {
+        Player::Black => Player::White,
+        Player::White => Player::Black,
+    }
+}
+
+pub fn check_pos(grd: &Board, p: Pos, c: i8) -> bool {
+    grd.contains(p) && grd.get(p) == c
+}
+
+pub fn empty_pos(grd: &Board) -> BitSet {
+    grd.empty()
+}
+
+pub fn valid_to_pos(v: &BitSet) -> Vec<Pos> {
+    v.iter().map(BitSet::pos).collect()
+}
+
+/// Removes from `vld` the cells where `player` would make a double free three. Under
+/// `captures`, a move that captures is allowed whatever threes it makes.
+pub fn del_double_three(grd: &Board, vld: &mut BitSet, player: Player, captures: bool) {
+    for i in vld.iter() {
+        if is_double_three(grd, BitSet::pos(i), player, captures) {
+            vld.remove(i);
+        }
+    }
+}
+
+/// Whether a `player` stone on the empty cell `p` makes free threes on two lines at once.
+pub fn is_double_three(grd: &Board, p: Pos, player: Player, captures: bool) -> bool {
+    if captures && countcap(grd, p, player) > 0 {
+        return false;
+    }
+    nb_free_three(grd, p, player_to_i8(player)) >= 2
+}
+
+/// Number of lines through the empty cell `p` on which a `c` stone there makes a free three.
+pub fn nb_free_three(grd: &Board, p: Pos, c: i8) -> usize {
+    (0..(NB_DIR / 2)).filter(|&i| is_free_three_dir(grd, p, ALL_DIR[i * 2], c)).count()
+}
+
+/// Whether a `c` stone on the empty cell `p` makes a free three along `d`: three stones that
+/// one more `c` stone on the line turns into an open four, exactly four stones in a row with
+/// an empty cell on the board at both ends.
+pub fn is_free_three_dir(grd: &Board, p: Pos, (dx, dy): (i16, i16), c: i8) -> bool {
+    let at = |k: i16| Pos { x: p.x + dx * k, y: p.y + dy * k };
+    if (-4..=4).filter(|&k| k != 0 && check_pos(grd, at(k), c)).count() < 2 {
+        return false;
+    }
+
+    for k in -4..=4 {
+        if k == 0 || !check_pos(grd, at(k), CELL_EMPTY) {
+            continue;
+        }
+        let is_c = |i: i16| i == 0 || i == k || check_pos(grd, at(i), c);
+        let mut back = 0;
+        while back < 4 && is_c(-back - 1) {
+            back += 1;
+        }
+        let mut front = 0;
+        while front < 4 && is_c(front + 1) {
+            front += 1;
+        }
+        if back + front + 1 == 4
+            && -back <= k
+            && k <= front
+            && check_pos(grd, at(-back - 1), CELL_EMPTY)
+            && check_pos(grd, at(front + 1), CELL_EMPTY) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Positions of the stones that `player` would capture by playing at `p`.
+pub fn listcap(grd: &Board, p: Pos, player: Player) -> Vec<Pos> {
+    let mut lcap: Vec<Pos> = Vec::new();
+
+    for &(dx, dy) in ALL_DIR.iter() {
+        let xy1 = Pos { x: p.x + dx, y: p.y + dy };
+        let xy2 = Pos { x: p.x + dx * 2, y: p.y + dy * 2 };
+        let xy3 = Pos { x: p.x + dx * 3, y: p.y + dy * 3 };
+
+        if !check_pos(grd, xy1, player_to_i8(next_player(player))) {
+            continue;
+        }
+        if !check_pos(grd, xy2, player_to_i8(next_player(player))) {
+            continue;
+        }
+        if !check_pos(grd, xy3, player_to_i8(player)) {
+            continue;
+        }
+        lcap.push(xy1);
+        lcap.push(xy2);
+    }
+    lcap
+}
+
+pub fn delcap(grd: &mut Board, p: Pos, player: Player) -> i16 {
+    let mut nb_del: i16 = 0;
+
+    for &(dx, dy) in ALL_DIR.iter() {
+        let xy1 = Pos { x: p.x + dx, y: p.y + dy };
+        let xy2 = Pos { x: p.x + dx * 2, y: p.y + dy * 2 };
+        let xy3 = Pos { x: p.x + dx * 3, y: p.y + dy * 3 };
+
+        if !check_pos(grd, xy1, player_to_i8(next_player(player))) {
+            continue;
+        }
+        if !check_pos(grd, xy2, player_to_i8(next_player(player))) {
+            continue;
+        }
+        if !check_pos(grd, xy3, player_to_i8(player)) {
+            continue;
+        }
+        grd.set(xy1, CELL_EMPTY);
+        grd.set(xy2, CELL_EMPTY);
+        nb_del += 2;
+    }
+    nb_del
+}
+
+pub fn countcap(grd: &Board, p: Pos, player: Player) -> i16 {
+    let mut nb_del: i16 = 0;
+
+    for &(dx, dy) in ALL_DIR.iter() {
+        let xy1 = Pos { x: p.x + dx, y: p.y + dy };
+        let xy2 = Pos { x: p.x + dx * 2, y: p.y + dy * 2 };
+        let xy3 = Pos { x: p.x + dx * 3, y: p.y + dy * 3 };
+
+        if !check_pos(grd, xy1, player_to_i8(next_player(player))) {
+            continue;
+        }
+        if !check_pos(grd, xy2, player_to_i8(next_player(player))) {
+            continue;
+        }
+        if !check_pos(grd, xy3, player_to_i8(player)) {
+            continue;
+        }
+        nb_del += 2;
+    }
+    nb_del
+}
+
+pub fn check_align_local(grd: &Board, Pos { x, y }: Pos, (dx, dy): (i16, i16), c: i8) -> i32 {
+    let mut nba: i16 = 1;
+    loop {
+        if !check_pos(grd, Pos { x: x + dx * nba, y: y + dy * nba }, c) {
+            break;
+        }
+        nba += 1;
+    }
+    (nba - 1) as i32
+}
+
+pub fn check_align_5p(grd: &Board, c: i8) -> bool {
+    let stones = grd.stones(c);
+    LINE_SHIFT.iter().any(|&k| !stones.runs(k, 5).is_empty())
+}
+
+/// Full scan of the board, see `check_end_local` for the one used while playing.
+// /!\ Slow
+pub fn check_end_grd(
+    grd: &Board,
+    nb_cap_white: i16,
+    nb_cap_black: i16,
+    player: Player,
+    last_move: Option<Pos>,
+    rules: &RuleSet,
+) -> Option<Player> {
+    if rules.has_five(grd, player) {
+        return Some(player);
+    }
+    if let Some(p) = last_move {
+        if !rules.has_five_at(grd, p, next_player(player)) {
+            return None;
+        }
+    } else if !rules.has_five(grd, next_player(player)) {
+        return None;
+    }
+    if !rules.capture.can_break() {
+        return Some(next_player(player));
+    }
+
+    {
+        let mut valid = empty_pos(grd);
+        let mut cp_grd: Board;
+        rules.del_forbidden(grd, &mut valid, player);
+
+        let nb_cap_player = match player {
+            Player::White => nb_cap_white,
+            Player::Black => nb_cap_black,
+        };
+
+        for p in valid.iter().map(BitSet::pos) {
+            cp_grd = *grd;
+            let nb_del = delcap(&mut cp_grd, p, player);
+            if nb_del == 0 {
+                continue;
+            }
+            if rules.is_cap_win(nb_cap_player + nb_del) {
+                return None;
+            }
+            if !rules.has_five(&cp_grd, next_player(player)) {
+                return None;
+            }
+        }
+    }
+
+    Some(next_player(player))
+}
+
+/// Whether a line of at least five `c` stones lies on one of the four whole lines through `p`.
+pub fn check_align_5_at(grd: &Board, p: Pos, c: i8) -> bool {
+    check_align_at(grd, p, c, 5, false, true, None)
+}
+
+/// Whether a line of `len` `c` stones, or more unless `exact`, lies on one of the four whole
+/// lines through `p`. Unless `blocked`, lines with opponent stones at both ends do not count.
+/// With `stones`, goes through all the lines and adds the stones of each one to it.
+pub fn check_align_at(
+    grd: &Board,
+    p: Pos,
+    c: i8,
+    len: usize,
+    exact: bool,
+    blocked: bool,
+    mut stones: Option<&mut Vec<Pos>>,
+) -> bool {
+    let opp = if c == CELL_BLACK { CELL_WHITE } else { CELL_BLACK };
+    let mut found = false;
+    for i in 0..(NB_DIR / 2) {
+        let (dx, dy) = ALL_DIR[i * 2];
+
+        let mut q = p;
+        while grd.contains(Pos { x: q.x - dx, y: q.y - dy }) {
+            q = Pos { x: q.x - dx, y: q.y - dy };
+        }
+
+        let mut nba = 0;
+        let mut opp_before = false;
+        loop {
+            let inside = grd.contains(q);
+            if inside && grd.get(q) == c {
+                nba += 1;
+            } else {
+                let opp_after = inside && grd.get(q) == opp;
+                if (nba == len || (nba > len && !exact)) && (blocked || !(opp_before && opp_after)) {
+                    let lpos = match stones.as_deref_mut() {
+                        Some(lpos) => lpos,
+                        None => return true,
+                    };
+                    found = true;
+                    for k in 1..=nba as i16 {
+                        let r = Pos { x: q.x - dx * k, y: q.y - dy * k };
+                        if !lpos.contains(&r) {
+                            lpos.push(r);
+                        }
+                    }
+                }
+                nba = 0;
+                opp_before = opp_after;
+            }
+            if !inside {
+                break;
+            }
+            q = Pos { x: q.x + dx, y: q.y + dy };
+        }
+    }
+    found
+}
+
+/// Empty cells where `player` captures at least one pair.
+pub fn capture_moves(grd: &Board, player: Player) -> BitSet {
+    let mut todo = BitSet::new();
+
+    for q in grd.stones(player_to_i8(player)).iter().map(BitSet::pos) {
+        for &(dx, dy) in ALL_DIR.iter() {
+            let xy1 = Pos { x: q.x + dx, y: q.y + dy };
+            let xy2 = Pos { x: q.x + dx * 2, y: q.y + dy * 2 };
+            let xy3 = Pos { x: q.x + dx * 3, y: q.y + dy * 3 };
+
+            if check_pos(grd, xy1, player_to_i8(next_player(player)))
+                && check_pos(grd, xy2, player_to_i8(next_player(player)))
+                && check_pos(grd, xy3, CELL_EMPTY) {
+                todo.insert(BitSet::index(xy3));
+            }
+        }
+    }
+    todo
+}
+
+/// Empty cells where a `player` stone would flank a pair of the opponent whose other end is
+/// empty, threatening to capture it next.
+pub fn capture_threats(grd: &Board, player: Player) -> BitSet {
+    let mut todo = BitSet::new();
+    let c = player_to_i8(next_player(player));
+
+    for q in grd.stones(c).iter().map(BitSet::pos) {
+        for &(dx, dy) in ALL_DIR.iter() {
+            let before = Pos { x: q.x - dx, y: q.y - dy };
+            let xy1 = Pos { x: q.x + dx, y: q.y + dy };
+            let xy2 = Pos { x: q.x + dx * 2, y: q.y + dy * 2 };
+
+            if check_pos(grd, xy1, c) && check_pos(grd, before, CELL_EMPTY) && check_pos(grd, xy2, CELL_EMPTY) {
+                todo.insert(BitSet::index(before));
+                todo.insert(BitSet::index(xy2));
+            }
+        }
+    }
+    todo
+}
+
+/// Incremental `check_end_grd` for positions reached by play.
+///
+/// `last_move` is the stone the opponent of `player` just placed and `prev_move` the last stone
+/// of `player`: any five on the board crosses one of them, so only the lines through those two
+/// cells are scanned, and only the capturing moves of `player` are tried to break a new five.
+pub fn check_end_local(
+    grd: &Board,
+    nb_cap_white: i16,
+    nb_cap_black: i16,
+    player: Player,
+    last_move: Pos,
+    prev_move: Option<Pos>,
+    rules: &RuleSet,
+) -> Option<Player> {
+    if let Some(p) = prev_move {
+        if rules.has_five_at(grd, p, player) {
+            return Some(player);
+        }
+    }
+    if !rules.has_five_at(grd, last_move, next_player(player)) {
+        return None;
+    }
+    if !rules.capture.can_break() {
+        return Some(next_player(player));
+    }
+
+    if five_breaks(grd, nb_cap_white, nb_cap_black, player, last_move, rules).is_empty() {
+        Some(next_player(player))
+    } else {
+        None
+    }
+}
+
+/// Moves of `player` answering the five of its opponent through `five_at`: the captures that
+/// break every winning line through that cell, or that reach the capture win.
+pub fn five_breaks(
+    grd: &Board,
+    nb_cap_white: i16,
+    nb_cap_black: i16,
+    player: Player,
+    five_at: Pos,
+    rules: &RuleSet,
+) -> BitSet {
+    let mut valid = capture_moves(grd, player);
+    let mut cp_grd: Board;
+    rules.del_forbidden(grd, &mut valid, player);
+
+    let nb_cap_player = match player {
+        Player::White => nb_cap_white,
+        Player::Black => nb_cap_black,
+    };
+
+    let mut breaks = BitSet::new();
+    for i in valid.iter() {
+        cp_grd = *grd;
+        let nb_del = delcap(&mut cp_grd, BitSet::pos(i), player);
+        if rules.is_cap_win(nb_cap_player + nb_del) || !rules.has_five_at(&cp_grd, five_at, next_player(player)) {
+            breaks.insert(i);
+        }
+    }
+    breaks
+}
+
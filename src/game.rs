@@ -0,0 +1,374 @@
+use std::error::Error;
+use std::fmt;
+use crate::board::*;
+use crate::opening::*;
+use crate::rules::{Forbidden, RuleSet};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IllegalMove {
+    GameOver,
+    OutOfBoard,
+    Occupied,
+    DoubleThree,
+    /// Renju foul for black.
+    Forbidden,
+    /// Black stone out of the place a Pro opening allows.
+    OpeningRestriction,
+    /// The opening waits for a swap answer, not a stone.
+    AwaitingChoice,
+    /// The opening does not wait for this swap answer.
+    UnexpectedChoice,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match self {
+            IllegalMove::GameOver => "the game is over",
+            IllegalMove::OutOfBoard => "position out of the board",
+            IllegalMove::Occupied => "cell already occupied",
+            IllegalMove::DoubleThree => "move creates a double three",
+            IllegalMove::Forbidden => "forbidden move for black",
+            IllegalMove::OpeningRestriction => "black stone too close to the centre, or off it",
+            IllegalMove::AwaitingChoice => "a colour has to be chosen first",
+            IllegalMove::UnexpectedChoice => "no such choice at this point",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+impl Error for IllegalMove {}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveOutcome {
+    pub player: Player,
+    pub pos: Pos,
+    pub captured: Vec<Pos>,
+    pub end: Option<Option<Player>>,
+}
+
+/// A five its owner has not won with yet, as the opponent may still break it by a capture.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PendingFive {
+    pub player: Player,
+    /// Stones of the winning lines.
+    pub stones: Vec<Pos>,
+    /// Moves of the opponent breaking it, any other one loses the game.
+    pub breaks: Vec<Pos>,
+}
+
+#[derive(Clone)]
+pub struct GameState {
+    board: Board,
+    rules: RuleSet,
+    player_turn: Player,
+    nb_cap_white: i16,
+    nb_cap_black: i16,
+    history: Vec<MoveOutcome>,
+    /// Moves taken back, with the swap answers given after each of them.
+    undone: Vec<(MoveOutcome, Vec<(usize, SwapChoice)>)>,
+    /// Swap answers, with the number of stones on the board when they were given.
+    choices: Vec<(usize, SwapChoice)>,
+    pending_five: Option<PendingFive>,
+    end: Option<Option<Player>>,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        GameState::new()
+    }
+}
+
+impl GameState {
+    pub fn new() -> Self {
+        GameState::with_size(DEFAULT_GRID_SIZE)
+    }
+
+    /// New game on a `size` x `size` board.
+    pub fn with_size(size: usize) -> Self {
+        GameState::with_rules(size, RuleSet::default())
+    }
+
+    pub fn with_rules(size: usize, rules: RuleSet) -> Self {
+        GameState {
+            board: Board::new(size),
+            rules,
+            player_turn: Player::Black,
+            nb_cap_white: 0,
+            nb_cap_black: 0,
+            history: Vec::new(),
+            undone: Vec::new(),
+            choices: Vec::new(),
+            pending_five: None,
+            end: None,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn player_turn(&self) -> Player {
+        self.player_turn
+    }
+
+    /// Number of stones captured by `player`.
+    pub fn nb_cap(&self, player: Player) -> i16 {
+        match player {
+            Player::White => self.nb_cap_white,
+            Player::Black => self.nb_cap_black,
+        }
+    }
+
+    /// Number of stones played since the start of the game.
+    pub fn nb_move(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Moves played so far, oldest first.
+    pub fn history(&self) -> &[MoveOutcome] {
+        &self.history
+    }
+
+    /// Last stone placed, by the opponent of the player to move unless in the middle of a
+    /// two-stone turn.
+    pub fn last_move(&self) -> Option<Pos> {
+        self.history.last().map(|o| o.pos)
+    }
+
+    /// Stone placed before `last_move`.
+    pub fn prev_move(&self) -> Option<Pos> {
+        self.history.iter().rev().nth(1).map(|o| o.pos)
+    }
+
+    fn swap_choices(&self) -> Vec<SwapChoice> {
+        self.choices.iter().map(|c| c.1).collect()
+    }
+
+    pub fn opening_step(&self) -> OpeningStep {
+        opening_step(self.rules.opening, self.history.len(), &self.swap_choices())
+    }
+
+    /// Whether the stones are still laid by the opening protocol rather than by their owners.
+    pub fn in_opening(&self) -> bool {
+        self.opening_step() != OpeningStep::Done
+    }
+
+    /// Seat that has to act now: lay a stone or answer a swap.
+    pub fn seat_to_move(&self) -> Seat {
+        match self.opening_step() {
+            OpeningStep::Place(seat) | OpeningStep::Choose(seat, _) => seat,
+            OpeningStep::Done => self.seat_of(self.player_turn),
+        }
+    }
+
+    /// Seat owning `player`'s stones, the first one until the opening has settled the colours.
+    pub fn seat_of(&self, player: Player) -> Seat {
+        let black = if self.in_opening() {
+            Seat::First
+        } else {
+            black_seat(self.rules.opening, &self.swap_choices())
+        };
+        if player == Player::Black { black } else { other_seat(black) }
+    }
+
+    /// Answers the swap the opening waits for.
+    pub fn choose(&mut self, choice: SwapChoice) -> Result<(), IllegalMove> {
+        match self.opening_step() {
+            OpeningStep::Choose(_, choices) if choices.contains(&choice) && self.end.is_none() => {
+                self.choices.push((self.history.len(), choice));
+                self.undone.clear();
+                Ok(())
+            }
+            _ => Err(IllegalMove::UnexpectedChoice),
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// `None` while the game goes on, `Some(None)` on a draw, `Some(Some(p))` when `p` won.
+    pub fn end(&self) -> Option<Option<Player>> {
+        self.end
+    }
+
+    pub fn winner(&self) -> Option<Player> {
+        self.end.unwrap_or_default()
+    }
+
+    /// Five the player to move has to break right now.
+    pub fn pending_five(&self) -> Option<&PendingFive> {
+        self.pending_five.as_ref()
+    }
+
+    fn update_pending_five(&mut self) {
+        self.pending_five = match self.history.last() {
+            Some(o) if self.end.is_none()
+                && self.rules.capture.can_break()
+                && self.rules.has_five_at(&self.board, o.pos, o.player) =>
+            {
+                let breaks = five_breaks(&self.board, self.nb_cap_white, self.nb_cap_black, self.player_turn, o.pos, &self.rules);
+                Some(PendingFive {
+                    player: o.player,
+                    stones: self.rules.five_stones_at(&self.board, o.pos, o.player),
+                    breaks: valid_to_pos(&breaks),
+                })
+            }
+            _ => None,
+        };
+    }
+
+    pub fn check_move(&self, p: Pos) -> Result<(), IllegalMove> {
+        if self.end.is_some() {
+            return Err(IllegalMove::GameOver);
+        }
+        if !self.board.contains(p) {
+            return Err(IllegalMove::OutOfBoard);
+        }
+        if self.board.get(p) != CELL_EMPTY {
+            return Err(IllegalMove::Occupied);
+        }
+        if let OpeningStep::Choose(..) = self.opening_step() {
+            return Err(IllegalMove::AwaitingChoice);
+        }
+
+        let mut valid = empty_pos(&self.board);
+        self.rules.del_opening(&self.board, &mut valid, self.player_turn);
+        if !valid.contains(p) {
+            return Err(IllegalMove::OpeningRestriction);
+        }
+        self.rules.del_forbidden(&self.board, &mut valid, self.player_turn);
+        if !valid.contains(p) {
+            return Err(match self.rules.forbidden {
+                Forbidden::Renju => IllegalMove::Forbidden,
+                _ => IllegalMove::DoubleThree,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn is_legal(&self, p: Pos) -> bool {
+        self.check_move(p).is_ok()
+    }
+
+    pub fn legal_moves(&self) -> Vec<Pos> {
+        if self.end.is_some() {
+            return Vec::new();
+        }
+        if let OpeningStep::Choose(..) = self.opening_step() {
+            return Vec::new();
+        }
+
+        let mut valid = empty_pos(&self.board);
+        self.rules.del_forbidden(&self.board, &mut valid, self.player_turn);
+        valid_to_pos(&valid)
+    }
+
+    /// Whether the player to move has no cell left, the board being full or every empty cell
+    /// forbidden to it.
+    fn no_move_left(&self) -> bool {
+        let mut valid = empty_pos(&self.board);
+        self.rules.del_forbidden(&self.board, &mut valid, self.player_turn);
+        valid.is_empty()
+    }
+
+    pub fn play(&mut self, p: Pos) -> Result<MoveOutcome, IllegalMove> {
+        let outcome = self.play_move(p)?;
+        self.undone.clear();
+        Ok(outcome)
+    }
+
+    /// Takes back the last move, putting its captured stones back on the board, along with
+    /// the swap answers given after it.
+    pub fn undo(&mut self) -> Option<MoveOutcome> {
+        let outcome = self.history.pop()?;
+        let nb_move = self.history.len();
+        let first_after = self.choices.iter().position(|c| c.0 > nb_move).unwrap_or(self.choices.len());
+        let choices = self.choices.split_off(first_after);
+
+        self.board.set(outcome.pos, CELL_EMPTY);
+        for p in outcome.captured.iter() {
+            self.board.set(*p, player_to_i8(next_player(outcome.player)));
+        }
+        if outcome.player == Player::Black {
+            self.nb_cap_black -= outcome.captured.len() as i16;
+        } else {
+            self.nb_cap_white -= outcome.captured.len() as i16;
+        }
+        self.player_turn = outcome.player;
+        self.end = None;
+        self.update_pending_five();
+
+        self.undone.push((outcome.clone(), choices));
+        Some(outcome)
+    }
+
+    /// Plays again the last move taken back by `undo`, and gives again the swap answers
+    /// that followed it.
+    pub fn redo(&mut self) -> Option<MoveOutcome> {
+        let (outcome, choices) = self.undone.pop()?;
+        match self.play_move(outcome.pos) {
+            Ok(outcome) => {
+                self.choices.extend(choices);
+                Some(outcome)
+            }
+            Err(_) => {
+                self.undone.push((outcome, choices));
+                None
+            }
+        }
+    }
+
+    fn play_move(&mut self, p: Pos) -> Result<MoveOutcome, IllegalMove> {
+        self.check_move(p)?;
+
+        let player = self.player_turn;
+        let opponent = next_player(player);
+        let prev_move = self.history.iter().rev().find(|o| o.player == opponent).map(|o| o.pos);
+        let pending_five = self.pending_five.take();
+        self.board.set(p, player_to_i8(player));
+        let (captured, cap) = if self.rules.capture.enabled {
+            (listcap(&self.board, p, player), delcap(&mut self.board, p, player))
+        } else {
+            (Vec::new(), 0)
+        };
+
+        if player == Player::Black {
+            self.nb_cap_black += cap;
+        } else {
+            self.nb_cap_white += cap;
+        }
+
+        self.player_turn = self.rules.player_of_move(self.history.len() + 1);
+
+        if self.rules.is_cap_win(self.nb_cap_black) {
+            self.end = Some(Some(Player::Black));
+        } else if self.rules.is_cap_win(self.nb_cap_white) {
+            self.end = Some(Some(Player::White));
+        } else if let Some(f) = pending_five.filter(|f| !f.breaks.contains(&p)) {
+            self.end = Some(Some(f.player));
+        } else if let Some(w) = check_end_local(&self.board, self.nb_cap_white, self.nb_cap_black, opponent, p, prev_move, &self.rules) {
+            self.end = Some(Some(w));
+        } else if self.no_move_left() {
+            self.end = Some(None);
+        }
+
+        let outcome = MoveOutcome {
+            player,
+            pos: p,
+            captured,
+            end: self.end,
+        };
+        self.history.push(outcome.clone());
+        self.update_pending_five();
+        Ok(outcome)
+    }
+}
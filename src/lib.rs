@@ -0,0 +1,18 @@
+//! Gomoku rules engine and solver, usable without the terminal UI.
+
+pub mod bitboard;
+pub mod board;
+pub mod game;
+pub mod opening;
+pub mod protocol;
+pub mod renju;
+pub mod rules;
+pub mod solver;
+pub mod tt;
+pub mod vcf;
+pub mod zobrist;
+
+pub use board::{Board, Player, Pos};
+pub use game::{GameState, IllegalMove, MoveOutcome, PendingFive};
+pub use opening::{Opening, OpeningStep, Seat, SwapChoice};
+pub use rules::{CaptureRules, RuleKind, RuleSet};
@@ -2,18 +2,58 @@ extern crate cursive;
 mod game_view;
 
 use cursive::Cursive;
+use cursive::view::Identifiable;
 use cursive::views::{Button, Dialog, LinearLayout, Panel};
-use game_view::{GameMode, GameView};
-use crate::game_view::Player;
+use game_view::{GameMode, GameView, GAME_VIEW_ID};
+use gomoku_rs::{CaptureRules, Opening, RuleKind, RuleSet, Seat};
 
-fn display_game(siv: &mut Cursive, game_mode: GameMode) {
+/// Capture win thresholds offered in the menu, `None` for captures that never win.
+const NB_CAP_WIN_CHOICES: [Option<i16>; 5] = [None, Some(6), Some(8), Some(10), Some(12)];
+
+/// Choices of the home menu, kept as the cursive user data.
+#[derive(Clone, Copy)]
+struct Settings {
+    rule_kind: RuleKind,
+    opening: Opening,
+    /// Capture rules overriding the ones of `rule_kind`.
+    capture: Option<CaptureRules>,
+}
+
+impl Settings {
+    fn capture(&self) -> CaptureRules {
+        let rules = RuleSet::new(self.rule_kind);
+        self.capture.map_or(rules, |c| rules.with_capture(c)).capture
+    }
+}
+
+fn settings(siv: &mut Cursive) -> Settings {
+    siv.user_data::<Settings>().cloned().unwrap_or(Settings {
+        rule_kind: RuleKind::FortyTwo,
+        opening: Opening::Free,
+        capture: None,
+    })
+}
+
+fn update_settings(siv: &mut Cursive, f: impl FnOnce(&mut Settings)) {
+    let mut todo = settings(siv);
+    f(&mut todo);
+    siv.set_user_data(todo);
+    siv.pop_layer();
+    siv.pop_layer();
+    display_home(siv);
+}
+
+fn display_game(siv: &mut Cursive, game_mode: GameMode, size: usize) {
+    let cb_sink = siv.cb_sink().clone();
+    let todo = settings(siv);
+    let rules = RuleSet::new(todo.rule_kind).with_opening(todo.opening).with_capture(todo.capture());
     siv.add_layer(
         Dialog::new()
             .title("Gomoku")
             .padding((6, 6, 2, 2))
             .content(
                 LinearLayout::horizontal()
-                    .child(Panel::new(GameView::new(game_mode))),
+                    .child(Panel::new(GameView::new(game_mode, size, rules, cb_sink).with_id(GAME_VIEW_ID))),
             )
             .button("Quit game", |s| {
                 s.pop_layer();
@@ -21,6 +61,20 @@ fn display_game(siv: &mut Cursive, game_mode: GameMode) {
     );
 }
 
+fn display_size_choice(siv: &mut Cursive, game_mode: GameMode) {
+    siv.add_layer(
+        Dialog::new()
+            .title("Board Size")
+            .padding((2, 2, 1, 1))
+            .content(
+                LinearLayout::vertical()
+                    .child(Button::new_raw(" 15x15 ", move |s| display_game(s, game_mode, 15)))
+                    .child(Button::new_raw(" 19x19 ", move |s| display_game(s, game_mode, 19)))
+                    .child(Button::new_raw("  Back  ", |s| { s.pop_layer(); })),
+            ),
+    );
+}
+
 fn display_turn_choice(siv: &mut Cursive) {
     siv.add_layer(
         Dialog::new()
@@ -28,22 +82,105 @@ fn display_turn_choice(siv: &mut Cursive) {
             .padding((2, 2, 1, 1))
             .content(
                 LinearLayout::vertical()
-                    .child(Button::new_raw(" First (black) ", |s| display_game(s, GameMode::Solo(Player::Black))))
-                    .child(Button::new_raw(" Second (white) ", |s| display_game(s, GameMode::Solo(Player::White))))
+                    .child(Button::new_raw(" First (black) ", |s| display_size_choice(s, GameMode::Solo(Seat::First))))
+                    .child(Button::new_raw(" Second (white) ", |s| display_size_choice(s, GameMode::Solo(Seat::Second))))
                     .child(Button::new_raw("     Back      ", |s| { s.pop_layer(); })),
             ),
     );
 }
 
+fn display_rules_choice(siv: &mut Cursive) {
+    let mut choices = LinearLayout::vertical();
+    for &kind in RuleKind::ALL.iter() {
+        choices.add_child(Button::new_raw(format!(" {:^10} ", kind.name()), move |s| {
+            update_settings(s, |todo| {
+                todo.rule_kind = kind;
+                todo.capture = None;
+            });
+        }));
+    }
+    choices.add_child(Button::new_raw(format!(" {:^10} ", "Back"), |s| { s.pop_layer(); }));
+
+    siv.add_layer(
+        Dialog::new()
+            .title("Rules")
+            .padding((2, 2, 1, 1))
+            .content(choices),
+    );
+}
+
+fn display_opening_choice(siv: &mut Cursive) {
+    let mut choices = LinearLayout::vertical();
+    for &opening in Opening::ALL.iter() {
+        choices.add_child(Button::new_raw(format!(" {:^10} ", opening.name()), move |s| {
+            update_settings(s, |todo| todo.opening = opening);
+        }));
+    }
+    choices.add_child(Button::new_raw(format!(" {:^10} ", "Back"), |s| { s.pop_layer(); }));
+
+    siv.add_layer(
+        Dialog::new()
+            .title("Opening")
+            .padding((2, 2, 1, 1))
+            .content(choices),
+    );
+}
+
+fn on_off(b: bool) -> &'static str {
+    if b { "On" } else { "Off" }
+}
+
+/// Applies `f` to the capture rules and shows the captures dialog again.
+fn update_capture(siv: &mut Cursive, f: impl FnOnce(&mut CaptureRules)) {
+    update_settings(siv, |todo| {
+        let mut capture = todo.capture();
+        f(&mut capture);
+        todo.capture = Some(capture);
+    });
+    display_capture_choice(siv);
+}
+
+fn display_capture_choice(siv: &mut Cursive) {
+    let capture = settings(siv).capture();
+    let nb_cap_win = capture.nb_cap_win.map_or("never".to_string(), |n| n.to_string());
+    siv.add_layer(
+        Dialog::new()
+            .title("Captures")
+            .padding((2, 2, 1, 1))
+            .content(
+                LinearLayout::vertical()
+                    .child(Button::new_raw(format!(" {:<14} ", format!("Captures: {}", on_off(capture.enabled))), |s| {
+                        update_capture(s, |c| c.enabled = !c.enabled);
+                    }))
+                    .child(Button::new_raw(format!(" {:<14} ", format!("Win at: {}", nb_cap_win)), |s| {
+                        update_capture(s, |c| {
+                            let i = NB_CAP_WIN_CHOICES.iter().position(|&n| n == c.nb_cap_win).unwrap_or(0);
+                            c.nb_cap_win = NB_CAP_WIN_CHOICES[(i + 1) % NB_CAP_WIN_CHOICES.len()];
+                        });
+                    }))
+                    .child(Button::new_raw(format!(" {:<14} ", format!("Break five: {}", on_off(capture.capture_break))), |s| {
+                        update_capture(s, |c| c.capture_break = !c.capture_break);
+                    }))
+                    .child(Button::new_raw(format!(" {:<14} ", "Back"), |s| { s.pop_layer(); })),
+            ),
+    );
+}
+
 fn display_home(siv: &mut Cursive) {
+    let rules = format!(" Rules: {} ", settings(siv).rule_kind.name());
+    let opening = format!(" Opening: {} ", settings(siv).opening.name());
+    let capture = format!(" Captures: {} ", on_off(settings(siv).capture().enabled));
     siv.add_layer(
         Dialog::new()
             .title("Gomoku")
             .padding((2, 2, 1, 1))
             .content(
                 LinearLayout::vertical()
-                    .child(Button::new_raw(" Multiplayer ", |s| display_game(s, GameMode::Multi)))
+                    .child(Button::new_raw(" Multiplayer ", |s| display_size_choice(s, GameMode::Multi)))
                     .child(Button::new_raw("    Solo    ", display_turn_choice))
+                    .child(Button::new_raw(rules, display_rules_choice))
+                    .child(Button::new_raw(opening, display_opening_choice))
+                    .child(Button::new_raw(capture, display_capture_choice))
                     .child(Button::new_raw("    Exit     ", |s| s.quit())),
             ),
     );
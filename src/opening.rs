@@ -0,0 +1,101 @@
+/// How the first stones are laid and the colours shared out.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Opening {
+    /// The first player is black and the players alternate from the first stone.
+    Free,
+    /// The first player lays three stones, then the second one picks a colour.
+    Swap,
+    /// As `Swap`, but the second player may also lay two more stones and let the first one
+    /// pick a colour.
+    Swap2,
+    /// As `Free`, with black's first stone on the centre and its second one at least 3 cells
+    /// away from it.
+    Pro,
+    /// As `Pro`, at least 4 cells away.
+    LongPro,
+}
+
+impl Opening {
+    pub const ALL: [Opening; 5] = [Opening::Free, Opening::Swap, Opening::Swap2, Opening::Pro, Opening::LongPro];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Opening::Free => "Free",
+            Opening::Swap => "Swap",
+            Opening::Swap2 => "Swap2",
+            Opening::Pro => "Pro",
+            Opening::LongPro => "Long Pro",
+        }
+    }
+
+    /// Least distance from the centre of black's second stone.
+    pub fn min_dist_second_black(self) -> Option<i16> {
+        match self {
+            Opening::Pro => Some(3),
+            Opening::LongPro => Some(4),
+            _ => None,
+        }
+    }
+}
+
+/// The player who starts the game, or the other one, whatever colours they end up with.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Seat {
+    First,
+    Second,
+}
+
+pub fn other_seat(seat: Seat) -> Seat {
+    match seat {
+        Seat::First => Seat::Second,
+        Seat::Second => Seat::First,
+    }
+}
+
+/// Answer to a swap: the colour the chooser takes, or two more stones in Swap2.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SwapChoice {
+    Black,
+    White,
+    PlaceTwo,
+}
+
+const CHOICES_COLOUR: [SwapChoice; 2] = [SwapChoice::Black, SwapChoice::White];
+const CHOICES_SWAP2: [SwapChoice; 3] = [SwapChoice::Black, SwapChoice::White, SwapChoice::PlaceTwo];
+
+/// What the opening waits for.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OpeningStep {
+    /// `Seat` lays the next stone, of the colour to move.
+    Place(Seat),
+    /// `Seat` answers the swap with one of the choices.
+    Choose(Seat, &'static [SwapChoice]),
+    /// The colours are known, each seat plays its own.
+    Done,
+}
+
+/// Step of `opening` after `nb_move` stones and the swap answers `choices`, oldest first.
+pub fn opening_step(opening: Opening, nb_move: usize, choices: &[SwapChoice]) -> OpeningStep {
+    match (opening, choices) {
+        (Opening::Free, _) | (Opening::Pro, _) | (Opening::LongPro, _) => OpeningStep::Done,
+        (_, []) if nb_move < 3 => OpeningStep::Place(Seat::First),
+        (Opening::Swap, []) => OpeningStep::Choose(Seat::Second, &CHOICES_COLOUR),
+        (Opening::Swap2, []) => OpeningStep::Choose(Seat::Second, &CHOICES_SWAP2),
+        (Opening::Swap2, [SwapChoice::PlaceTwo]) if nb_move < 5 => OpeningStep::Place(Seat::Second),
+        (Opening::Swap2, [SwapChoice::PlaceTwo]) => OpeningStep::Choose(Seat::First, &CHOICES_COLOUR),
+        _ => OpeningStep::Done,
+    }
+}
+
+/// Seat playing black once the opening is `Done`.
+pub fn black_seat(opening: Opening, choices: &[SwapChoice]) -> Seat {
+    let chooser = match (opening, choices) {
+        (Opening::Free, _) | (Opening::Pro, _) | (Opening::LongPro, _) | (_, []) => return Seat::First,
+        (_, [SwapChoice::PlaceTwo, ..]) => Seat::First,
+        _ => Seat::Second,
+    };
+    match choices.last() {
+        Some(SwapChoice::Black) => chooser,
+        _ => other_seat(chooser),
+    }
+}
@@ -0,0 +1,25 @@
+//! Engine for the Gomocup / Piskvork protocol, one command per line on stdin.
+
+use std::io::{self, BufRead, Write};
+use gomoku_rs::protocol::Brain;
+
+fn main() {
+    let mut brain = Brain::new();
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => break,
+        };
+        if line.trim().eq_ignore_ascii_case("END") {
+            break;
+        }
+        if let Some(answer) = brain.command(&line) {
+            let mut out = stdout.lock();
+            let _ = writeln!(out, "{}", answer);
+            let _ = out.flush();
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Engine side of the Gomocup / Piskvork protocol, one command per line.
+
+use std::time::Duration;
+use crate::board::{DEFAULT_GRID_SIZE, MAX_GRID_SIZE, MIN_GRID_SIZE};
+use crate::solver::{Solver, SolverConfig, DEFAULT_TIME_LIMIT_MS};
+use crate::tt::DEFAULT_TT_SIZE_MB;
+use crate::{GameState, Pos, RuleKind, RuleSet};
+
+/// Time kept aside on each move for the answer to reach the manager.
+const TIME_MARGIN_MS: u64 = 30;
+/// Share of the remaining match time one move may use.
+const NB_MOVE_LEFT_EST: u64 = 20;
+/// Time for one move when the manager asks for answers as fast as possible.
+const FASTEST_MOVE_MS: u64 = 50;
+
+/// Bits of `INFO rule`, freestyle when none is set.
+const RULE_EXACT_FIVE: u64 = 1;
+const RULE_RENJU: u64 = 4;
+const RULE_CARO: u64 = 8;
+
+const FIELD_OWN: u8 = 1;
+const FIELD_OPPONENT: u8 = 2;
+
+/// Engine state between two commands: the game, its rules and the time allowed.
+pub struct Brain {
+    game: GameState,
+    rules: RuleSet,
+    solver: Solver,
+    /// Time for one move, 0 for as fast as possible.
+    timeout_turn: u64,
+    /// Time for the whole match, `None` when it is unlimited.
+    timeout_match: Option<u64>,
+    /// Match time left, once the manager has told it.
+    time_left: Option<u64>,
+    tt_size_mb: usize,
+    /// Stones of the BOARD command being read, with their field.
+    board_cmd: Option<Vec<(Pos, u8)>>,
+}
+
+impl Default for Brain {
+    fn default() -> Self {
+        Brain::new()
+    }
+}
+
+impl Brain {
+    pub fn new() -> Self {
+        Brain {
+            game: GameState::with_rules(DEFAULT_GRID_SIZE, RuleSet::new(RuleKind::Freestyle)),
+            rules: RuleSet::new(RuleKind::Freestyle),
+            solver: Solver::default(),
+            timeout_turn: DEFAULT_TIME_LIMIT_MS,
+            timeout_match: None,
+            time_left: None,
+            tt_size_mb: DEFAULT_TT_SIZE_MB,
+            board_cmd: None,
+        }
+    }
+
+    pub fn game(&self) -> &GameState {
+        &self.game
+    }
+
+    /// Time for the next move, within the turn limit and a share of the match time left, or
+    /// of the whole match time before the manager tells what is left of it.
+    pub fn time_limit(&self) -> Duration {
+        let turn = if self.timeout_turn == 0 { FASTEST_MOVE_MS } else { self.timeout_turn };
+        let limit = match self.time_left.or(self.timeout_match) {
+            Some(l) => turn.min(l / NB_MOVE_LEFT_EST),
+            None => turn,
+        };
+        Duration::from_millis(limit.saturating_sub(TIME_MARGIN_MS).max(1))
+    }
+
+    fn play_own(&mut self) -> String {
+        self.solver.set_time_limit(Some(self.time_limit()));
+        let res = self.solver.search(&self.game);
+        match self.game.play(res.best_move) {
+            Ok(_) => format!(
+                "MESSAGE depth {} score {} time {} ms pv {}\n{},{}",
+                res.depth,
+                res.score,
+                res.time.as_millis(),
+                res.pv.iter().map(|p| format!("{},{}", p.x, p.y)).collect::<Vec<_>>().join(" "),
+                res.best_move.x,
+                res.best_move.y,
+            ),
+            Err(e) => format!("ERROR {}", e),
+        }
+    }
+
+    fn parse_pos(&self, s: &str) -> Option<Pos> {
+        let mut it = s.split(',').map(|v| v.trim().parse::<i16>());
+        match (it.next(), it.next()) {
+            (Some(Ok(x)), Some(Ok(y))) => Some(Pos { x, y }),
+            _ => None,
+        }
+    }
+
+    fn start(&mut self, arg: &str) -> String {
+        match arg.trim().parse::<usize>() {
+            Ok(size) if (MIN_GRID_SIZE..=MAX_GRID_SIZE).contains(&size) => {
+                self.game = GameState::with_rules(size, self.rules);
+                self.solver.clear();
+                "OK".to_string()
+            }
+            _ => format!("ERROR unsupported size, {} to {}", MIN_GRID_SIZE, MAX_GRID_SIZE),
+        }
+    }
+
+    fn info(&mut self, arg: &str) {
+        let mut it = arg.split_whitespace();
+        let (key, value) = match (it.next(), it.next().and_then(|v| v.parse::<u64>().ok())) {
+            (Some(k), Some(v)) => (k, v),
+            _ => return,
+        };
+        match key {
+            "timeout_turn" => self.timeout_turn = value,
+            "time_left" => self.time_left = Some(value),
+            "timeout_match" if value == 0 => {
+                self.timeout_match = None;
+                self.time_left = None;
+            }
+            "timeout_match" => self.timeout_match = Some(value),
+            "rule" => {
+                let kind = if value & RULE_RENJU != 0 {
+                    RuleKind::Renju
+                } else if value & RULE_CARO != 0 {
+                    RuleKind::Caro
+                } else if value & RULE_EXACT_FIVE != 0 {
+                    RuleKind::Standard
+                } else {
+                    RuleKind::Freestyle
+                };
+                self.rules = RuleSet::new(kind);
+                if self.game.nb_move() == 0 {
+                    self.game = GameState::with_rules(self.game.board().size(), self.rules);
+                }
+            }
+            "max_memory" => {
+                // Half of the allowed memory goes to the transposition table.
+                let mb = if value == 0 { DEFAULT_TT_SIZE_MB } else { ((value >> 20) / 2).max(1) as usize };
+                if mb != self.tt_size_mb {
+                    self.tt_size_mb = mb;
+                    self.solver = Solver::new(SolverConfig { tt_size_mb: mb, ..SolverConfig::default() });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Rebuilds the game from the BOARD stones, alternating from the side with more stones.
+    fn board_done(&mut self, stones: Vec<(Pos, u8)>) -> String {
+        let own: Vec<Pos> = stones.iter().filter(|s| s.1 == FIELD_OWN).map(|s| s.0).collect();
+        let opp: Vec<Pos> = stones.iter().filter(|s| s.1 == FIELD_OPPONENT).map(|s| s.0).collect();
+        if own.len() > opp.len() || opp.len() > own.len() + 1 {
+            return "ERROR stones out of turn".to_string();
+        }
+
+        self.game = GameState::with_rules(self.game.board().size(), self.rules);
+        let mut first = if opp.len() > own.len() { &opp } else { &own }.iter();
+        let mut second = if opp.len() > own.len() { &own } else { &opp }.iter();
+        loop {
+            for p in first.next().into_iter().chain(second.next()) {
+                if let Err(e) = self.game.play(*p) {
+                    return format!("ERROR {} at {},{}", e, p.x, p.y);
+                }
+            }
+            if first.len() == 0 {
+                break;
+            }
+        }
+        self.play_own()
+    }
+
+    /// Answers one line, `None` when the line needs no answer.
+    pub fn command(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+        if let Some(stones) = self.board_cmd.as_mut() {
+            if line.eq_ignore_ascii_case("DONE") {
+                let stones = self.board_cmd.take().unwrap();
+                return Some(self.board_done(stones));
+            }
+            let mut it = line.split(',').map(|v| v.trim().parse::<i16>());
+            if let (Some(Ok(x)), Some(Ok(y)), Some(Ok(f))) = (it.next(), it.next(), it.next()) {
+                stones.push((Pos { x, y }, f as u8));
+            }
+            return None;
+        }
+
+        let (cmd, arg) = match line.find(' ') {
+            Some(i) => (&line[..i], &line[i + 1..]),
+            None => (line, ""),
+        };
+        match cmd.to_ascii_uppercase().as_str() {
+            "" => None,
+            "START" => Some(self.start(arg)),
+            "RESTART" => {
+                self.game = GameState::with_rules(self.game.board().size(), self.rules);
+                Some("OK".to_string())
+            }
+            "BEGIN" => Some(self.play_own()),
+            "TURN" => match self.parse_pos(arg) {
+                Some(p) => match self.game.play(p) {
+                    Ok(_) => Some(self.play_own()),
+                    Err(e) => Some(format!("ERROR {}", e)),
+                },
+                None => Some("ERROR bad coordinates".to_string()),
+            },
+            "BOARD" => {
+                self.board_cmd = Some(Vec::new());
+                None
+            }
+            "TAKEBACK" => match self.parse_pos(arg) {
+                Some(p) if self.game.last_move() == Some(p) => {
+                    self.game.undo();
+                    Some("OK".to_string())
+                }
+                _ => Some("ERROR not the last move".to_string()),
+            },
+            "INFO" => {
+                self.info(arg);
+                None
+            }
+            "ABOUT" => Some(format!(
+                "name=\"{}\", version=\"{}\", author=\"{}\", country=\"FR\"",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION"),
+                env!("CARGO_PKG_AUTHORS"),
+            )),
+            _ => Some(format!("UNKNOWN command {}", cmd)),
+        }
+    }
+}
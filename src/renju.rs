@@ -0,0 +1,135 @@
+use crate::bitboard::BitSet;
+use crate::board::*;
+
+/// Nested forbidden checks done to tell a real three, deeper ones are taken as allowed.
+const MAX_DEPTH_THREE: u32 = 3;
+
+/// Removes from `vld` the cells where black may not play under Renju rules.
+pub fn del_forbidden_black(grd: &Board, vld: &mut BitSet) {
+    for i in vld.iter() {
+        if is_forbidden(grd, BitSet::pos(i)) {
+            vld.remove(i);
+        }
+    }
+}
+
+/// Whether a black stone on the empty cell `p` is a foul: an overline, a double four or a
+/// double three, unless it also makes an exact five. A three only counts if it can become a
+/// straight four by a move that is not itself forbidden.
+pub fn is_forbidden(grd: &Board, p: Pos) -> bool {
+    forbidden(grd, p, 0)
+}
+
+fn forbidden(grd: &Board, p: Pos, depth: u32) -> bool {
+    if !near_black_lines(grd, p) {
+        return false;
+    }
+
+    let mut cp_grd = *grd;
+    cp_grd.set(p, CELL_BLACK);
+
+    let mut overline = false;
+    for i in 0..(NB_DIR / 2) {
+        let nba = 1 + check_align_local(&cp_grd, p, ALL_DIR[i * 2], CELL_BLACK)
+            + check_align_local(&cp_grd, p, ALL_DIR[i * 2 + 1], CELL_BLACK);
+        if nba == 5 {
+            return false;
+        }
+        if nba > 5 {
+            overline = true;
+        }
+    }
+    if overline {
+        return true;
+    }
+
+    let mut nb_four = 0;
+    let mut four_dir = [false; NB_DIR / 2];
+    for i in 0..(NB_DIR / 2) {
+        let n = nb_four_dir(&cp_grd, p, ALL_DIR[i * 2]);
+        four_dir[i] = n > 0;
+        nb_four += n;
+    }
+    if nb_four >= 2 {
+        return true;
+    }
+    if depth >= MAX_DEPTH_THREE {
+        return false;
+    }
+
+    let mut nb_three = 0;
+    for i in 0..(NB_DIR / 2) {
+        if !four_dir[i] && is_three_dir(&cp_grd, p, ALL_DIR[i * 2], depth) {
+            nb_three += 1;
+        }
+    }
+    nb_three >= 2
+}
+
+/// Only a cell with two black stones on two of its lines, or three on one, can be a foul.
+fn near_black_lines(grd: &Board, p: Pos) -> bool {
+    let mut nb_line = 0;
+    for i in 0..(NB_DIR / 2) {
+        let (dx, dy) = ALL_DIR[i * 2];
+        let mut nb = 0;
+        for k in -5..=5 {
+            if k != 0 && check_pos(grd, Pos { x: p.x + dx * k, y: p.y + dy * k }, CELL_BLACK) {
+                nb += 1;
+            }
+        }
+        if nb >= 3 {
+            return true;
+        }
+        if nb >= 2 {
+            nb_line += 1;
+        }
+    }
+    nb_line >= 2
+}
+
+/// Offsets from `p` along `d` of the empty cells that would make an exact five through `p`.
+fn five_points(grd: &Board, p: Pos, (dx, dy): (i16, i16)) -> Vec<i16> {
+    let mut lk = Vec::new();
+    for k in -4..=4 {
+        let q = Pos { x: p.x + dx * k, y: p.y + dy * k };
+        if k == 0 || !check_pos(grd, q, CELL_EMPTY) {
+            continue;
+        }
+        let mut cp_grd = *grd;
+        cp_grd.set(q, CELL_BLACK);
+        let back = check_align_local(&cp_grd, q, (-dx, -dy), CELL_BLACK) as i16;
+        let front = check_align_local(&cp_grd, q, (dx, dy), CELL_BLACK) as i16;
+        if 1 + back + front == 5 && -back <= -k && -k <= front {
+            lk.push(k);
+        }
+    }
+    lk
+}
+
+/// Fours through `p` along `d`. A straight four counts once, two fours sharing the line twice.
+fn nb_four_dir(grd: &Board, p: Pos, d: (i16, i16)) -> usize {
+    let lk = five_points(grd, p, d);
+    if lk.len() == 2 && lk[1] - lk[0] == 5 {
+        1
+    } else {
+        lk.len()
+    }
+}
+
+/// Whether `p` is part of a three along `d`: one more allowed black stone on that line makes
+/// a straight four through `p`.
+fn is_three_dir(grd: &Board, p: Pos, (dx, dy): (i16, i16), depth: u32) -> bool {
+    for k in -4..=4 {
+        let q = Pos { x: p.x + dx * k, y: p.y + dy * k };
+        if k == 0 || !check_pos(grd, q, CELL_EMPTY) {
+            continue;
+        }
+        let mut cp_grd = *grd;
+        cp_grd.set(q, CELL_BLACK);
+        let lk = five_points(&cp_grd, p, (dx, dy));
+        if lk.len() == 2 && lk[1] - lk[0] == 5 && !forbidden(grd, q, depth + 1) {
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,252 @@
+use crate::bitboard::{BitSet, LINE_SHIFT};
+use crate::board::*;
+use crate::opening::Opening;
+use crate::renju;
+
+/// Named rule sets, as offered in the menus.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RuleKind {
+    Freestyle,
+    Standard,
+    Renju,
+    Pente,
+    FortyTwo,
+    Connect6,
+    Caro,
+}
+
+impl RuleKind {
+    pub const ALL: [RuleKind; 7] = [
+        RuleKind::FortyTwo,
+        RuleKind::Freestyle,
+        RuleKind::Standard,
+        RuleKind::Renju,
+        RuleKind::Pente,
+        RuleKind::Connect6,
+        RuleKind::Caro,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RuleKind::Freestyle => "Freestyle",
+            RuleKind::Standard => "Standard",
+            RuleKind::Renju => "Renju",
+            RuleKind::Pente => "Pente",
+            RuleKind::FortyTwo => "42",
+            RuleKind::Connect6 => "Connect6",
+            RuleKind::Caro => "Caro",
+        }
+    }
+}
+
+/// Whether a line longer than `win_len` wins.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Overline {
+    Win,
+    NoWin,
+    /// Only black is denied the overline, as in Renju.
+    BlackNoWin,
+}
+
+/// Moves a player may not play.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Forbidden {
+    None,
+    /// Double free three, for both players, unless the move captures.
+    DoubleThree,
+    /// Renju fouls, for black only: double three, double four and overline.
+    Renju,
+}
+
+/// Captures: a pair of stones flanked by two opponent stones is removed from the board.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CaptureRules {
+    pub enabled: bool,
+    /// Captured stones needed to win, `None` when captures never win.
+    pub nb_cap_win: Option<i16>,
+    /// Whether a five only wins if the opponent cannot break it by a capture.
+    pub capture_break: bool,
+}
+
+impl CaptureRules {
+    pub const NONE: CaptureRules = CaptureRules { enabled: false, nb_cap_win: None, capture_break: false };
+
+    pub fn is_cap_win(&self, nb_cap: i16) -> bool {
+        self.enabled && self.nb_cap_win.is_some_and(|n| nb_cap >= n)
+    }
+
+    /// Whether a five has to survive the opponent's captures before it wins.
+    pub fn can_break(&self) -> bool {
+        self.enabled && self.capture_break
+    }
+}
+
+/// Everything that differs between the gomoku variants: win condition, forbidden moves and
+/// captures. `GameState` and the solver both follow the rule set of the game.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RuleSet {
+    pub kind: RuleKind,
+    /// Stones in a row needed to win.
+    pub win_len: usize,
+    /// Stones laid by each turn but black's first one, which is always a single stone.
+    pub stones_per_turn: usize,
+    pub overline: Overline,
+    /// Whether a line with opponent stones at both ends wins, which it does not in Caro.
+    pub blocked_wins: bool,
+    pub forbidden: Forbidden,
+    pub capture: CaptureRules,
+    pub opening: Opening,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet::new(RuleKind::FortyTwo)
+    }
+}
+
+impl RuleSet {
+    pub fn new(kind: RuleKind) -> Self {
+        let base = RuleSet {
+            kind,
+            win_len: 5,
+            stones_per_turn: 1,
+            overline: Overline::Win,
+            blocked_wins: true,
+            forbidden: Forbidden::None,
+            capture: CaptureRules::NONE,
+            opening: Opening::Free,
+        };
+        match kind {
+            RuleKind::Freestyle => base,
+            RuleKind::Standard => RuleSet { overline: Overline::NoWin, ..base },
+            RuleKind::Renju => RuleSet { overline: Overline::BlackNoWin, forbidden: Forbidden::Renju, ..base },
+            RuleKind::Pente => RuleSet {
+                capture: CaptureRules { enabled: true, nb_cap_win: Some(NB_CAP_WIN), capture_break: false },
+                ..base
+            },
+            RuleKind::FortyTwo => RuleSet {
+                forbidden: Forbidden::DoubleThree,
+                capture: CaptureRules { enabled: true, nb_cap_win: Some(NB_CAP_WIN), capture_break: true },
+                ..base
+            },
+            RuleKind::Connect6 => RuleSet { win_len: 6, stones_per_turn: 2, ..base },
+            RuleKind::Caro => RuleSet { blocked_wins: false, ..base },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    pub fn with_opening(self, opening: Opening) -> Self {
+        RuleSet { opening, ..self }
+    }
+
+    /// Captures are left off with two-stone turns, whose moves the search counts by the stones
+    /// on the board.
+    pub fn with_capture(self, capture: CaptureRules) -> Self {
+        if self.stones_per_turn > 1 {
+            return self;
+        }
+        RuleSet { capture, ..self }
+    }
+
+    /// Player laying the stone number `nb_move`, counted from 0.
+    pub fn player_of_move(&self, nb_move: usize) -> Player {
+        if nb_move == 0 || ((nb_move - 1) / self.stones_per_turn) % 2 == 1 {
+            Player::Black
+        } else {
+            Player::White
+        }
+    }
+
+    /// Round of the stone number `nb_move`, counted from 1: a turn of black and the turn of
+    /// white that follows it.
+    pub fn round_of_move(&self, nb_move: usize) -> usize {
+        (0..=nb_move)
+            .filter(|&n| self.player_of_move(n) == Player::Black && (n == 0 || self.player_of_move(n - 1) == Player::White))
+            .count()
+    }
+
+    pub fn overline_wins(&self, player: Player) -> bool {
+        match self.overline {
+            Overline::Win => true,
+            Overline::NoWin => false,
+            Overline::BlackNoWin => player == Player::White,
+        }
+    }
+
+    pub fn is_cap_win(&self, nb_cap: i16) -> bool {
+        self.capture.is_cap_win(nb_cap)
+    }
+
+    /// Removes from `vld` the cells `player` may not play.
+    pub fn del_forbidden(&self, grd: &Board, vld: &mut BitSet, player: Player) {
+        self.del_opening(grd, vld, player);
+        match self.forbidden {
+            Forbidden::None => (),
+            Forbidden::DoubleThree => del_double_three(grd, vld, player, self.capture.enabled),
+            Forbidden::Renju => {
+                if player == Player::Black {
+                    renju::del_forbidden_black(grd, vld);
+                }
+            }
+        }
+    }
+
+    /// Removes the cells a Pro opening keeps black off: all but the centre for its first stone,
+    /// the square around the centre for its second one.
+    pub fn del_opening(&self, grd: &Board, vld: &mut BitSet, player: Player) {
+        let dist = match self.opening.min_dist_second_black() {
+            Some(d) if player == Player::Black => d,
+            _ => return,
+        };
+        let nb_black = grd.stones(CELL_BLACK).count();
+        let nb_stone = grd.occupied().count();
+        let c = grd.center();
+        if nb_stone == 0 {
+            let center = vld.contains(c);
+            *vld = BitSet::new();
+            if center {
+                vld.insert(BitSet::index(c));
+            }
+        } else if nb_black == 1 && nb_stone == 2 {
+            for i in vld.iter() {
+                let p = BitSet::pos(i);
+                if (p.x - c.x).abs().max((p.y - c.y).abs()) < dist {
+                    vld.remove(i);
+                }
+            }
+        }
+    }
+
+    /// Whether `player` has a winning line anywhere on the board.
+    pub fn has_five(&self, grd: &Board, player: Player) -> bool {
+        let stones = grd.stones(player_to_i8(player));
+        if !self.blocked_wins {
+            return stones.iter().any(|i| self.has_five_at(grd, BitSet::pos(i), player));
+        }
+        let exact = !self.overline_wins(player);
+        LINE_SHIFT.iter().any(|&k| {
+            let mut starts = stones.runs(k, self.win_len);
+            if exact {
+                starts = starts.and_not(&stones.runs(k, self.win_len + 1)).and_not(&stones.shl(k));
+            }
+            !starts.is_empty()
+        })
+    }
+
+    /// Whether `player` has a winning line on one of the four whole lines through `p`.
+    pub fn has_five_at(&self, grd: &Board, p: Pos, player: Player) -> bool {
+        let (c, exact) = (player_to_i8(player), !self.overline_wins(player));
+        check_align_at(grd, p, c, self.win_len, exact, self.blocked_wins, None)
+    }
+
+    /// Stones of the winning lines of `player` through `p`.
+    pub fn five_stones_at(&self, grd: &Board, p: Pos, player: Player) -> Vec<Pos> {
+        let mut stones = Vec::new();
+        let (c, exact) = (player_to_i8(player), !self.overline_wins(player));
+        check_align_at(grd, p, c, self.win_len, exact, self.blocked_wins, Some(&mut stones));
+        stones
+    }
+}
@@ -0,0 +1,1157 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::bitboard::{BitSet, NB_BITS};
+use crate::board::*;
+use crate::game::GameState;
+use crate::opening::{OpeningStep, SwapChoice};
+use crate::rules::RuleSet;
+use crate::tt::{Bound, TTEntry, TranspositionTable, DEFAULT_TT_SIZE_MB};
+use crate::vcf::{self, Limits, MAX_DEPTH_VCF, MAX_DEPTH_VCT};
+use crate::zobrist;
+
+pub const MAX_DEPTH: i16 = 20;
+pub const DEFAULT_TIME_LIMIT_MS: u64 = 500;
+const DEPTH_MALUS: i32 = 100;
+
+const SCORE_CAP: i32 = 200;
+const SCORE_ALIGN_1: i32 = 1;
+const SCORE_ALIGN_2: i32 = 10;
+const SCORE_ALIGN_3: i32 = 100;
+const SCORE_ALIGN_4: i32 = 1000;
+const SCORE_ALIGN_5: i32 = 1000000;
+
+pub const INF: i32 = i32::MAX / 2;
+const SCORE_MAX: i32 = INF / 2;
+/// Lowest score of a won position, whatever the depth it is found at.
+const SCORE_WIN_MIN: i32 = SCORE_MAX - 64 * DEPTH_MALUS;
+const SCORE_BREAK: i32 = SCORE_ALIGN_5 / 2;
+const SCORE_NB_V: i32 = 1;
+/// Score of a position where the player to move has no cell left.
+const SCORE_DRAW: i32 = 0;
+/// Opening positions closer to even than this are left to the opponent to pick from.
+const SCORE_BALANCED: i32 = SCORE_ALIGN_3;
+/// Depth of the search that rates each opening stone.
+const DEPTH_OPENING: i16 = 2;
+/// Plies past the horizon where fours are still played, and where captures are.
+const DEPTH_QUIESCENCE: i16 = 8;
+const DEPTH_QUIESCENCE_CAPTURE: i16 = 2;
+/// Half width of the first aspiration window, and its growth on each failure.
+const ASPIRATION_DELTA: i32 = SCORE_ALIGN_3;
+const ASPIRATION_GROWTH: i32 = 4;
+/// Ordering bonus of the killer moves of a ply, ahead of quiet moves but behind threats.
+const SCORE_KILLER: i32 = SCORE_ALIGN_3;
+/// History count at which all the counts are halved. A move at the cap gets a bonus just below
+/// the killers'.
+const HISTORY_MAX: i32 = 1 << 16;
+/// Share of the time of a move the threat searches run in, before the iterations: a quarter.
+const THREAT_TIME_DIV: u32 = 4;
+/// Share of the time of a move kept aside for the search to wind down: a tenth.
+const TIME_MARGIN_DIV: u32 = 10;
+
+fn del_dist_1(v: &BitSet, size: usize) -> BitSet {
+    let invalid = BitSet::inside(size).and_not(v);
+    *v & invalid.dilate()
+}
+
+/// Score of a line of `nba` stones, the line one stone short of `win_len` scoring as a four.
+fn nba_to_score(nba: i32, win_len: usize) -> i32 {
+    match nba + 5 - win_len as i32 {
+        n if n <= 0 => 0,
+        1 => SCORE_ALIGN_1,
+        2 => SCORE_ALIGN_2,
+        3 => SCORE_ALIGN_3,
+        4 => SCORE_ALIGN_4,
+        _ => SCORE_ALIGN_5,
+    }
+}
+
+/// Value of one captured stone: the closer captures get to a win, the more they are worth.
+fn score_cap(rules: &RuleSet) -> i32 {
+    match rules.capture.nb_cap_win {
+        Some(n) => SCORE_CAP * (NB_CAP_WIN as i32) / (n.max(1) as i32),
+        None => SCORE_CAP / 2,
+    }
+}
+
+fn scoring_ordoring(
+    grd: &Board,
+    p: Pos,
+    player: Player,
+    nb_cap_white: i16,
+    nb_cap_black: i16,
+    rules: &RuleSet,
+) -> i32 {
+    let mut score: i32 = 0;
+
+    for i in 0..(NB_DIR / 2) {
+        let ab = 1 + check_align_local(grd, p, ALL_DIR[i * 2], CELL_BLACK)
+            + check_align_local(grd, p, ALL_DIR[i * 2 + 1], CELL_BLACK);
+        let aw = 1 + check_align_local(grd, p, ALL_DIR[i * 2], CELL_WHITE)
+            + check_align_local(grd, p, ALL_DIR[i * 2 + 1], CELL_WHITE);
+
+        score += nba_to_score(ab, rules.win_len);
+        score += nba_to_score(aw, rules.win_len);
+        if !rules.capture.enabled {
+            continue;
+        }
+        let cap_b = countcap(grd, p, Player::Black) as i32;
+        score += (cap_b) * score_cap(rules);
+        let cap_w = countcap(grd, p, Player::White) as i32;
+        score += (cap_w) * score_cap(rules);
+
+        if rules.is_cap_win(nb_cap_white + (cap_w as i16)) || rules.is_cap_win(nb_cap_black + (cap_b as i16)) {
+            return SCORE_MAX;
+        }
+    }
+
+    let mut nb_v = 0;
+    for &(dx, dy) in ALL_DIR.iter() {
+
+        if check_pos(grd, Pos { x: p.x + dx, y: p.y + dy }, player_to_i8(player)) {
+            nb_v += 2;
+        }
+        if check_pos(grd, Pos { x: p.x + dx, y: p.y + dy }, player_to_i8(next_player(player))) {
+            nb_v += 1;
+        }
+    }
+    score += nb_v * SCORE_NB_V;
+
+    score
+}
+
+/// Score of a line of `nba` stones that has no room left to win. A line long enough to have won
+/// already, only left on the board by Caro's blocked ends, is worth nothing.
+fn dead_line_score(ds: i32, nba: i32, rules: &RuleSet) -> i32 {
+    if nba as usize >= rules.win_len {
+        0
+    } else {
+        ds / 22
+    }
+}
+
+fn scoring_align(grd: &Board, player: Player, rules: &RuleSet) -> i32 {
+    let mut score: i32 = 0;
+    let c = player_to_i8(player);
+
+    fn check_can_deploy(grd: &Board, p: Pos, pprev: Pos, c: i8, rules: &RuleSet) -> bool {
+        let Pos { x, y } = pprev;
+        let dx = p.x - pprev.x;
+        let dy = p.y - pprev.y;
+
+        let mut nba1: i16 = 1;
+        loop {
+            if !(check_pos(grd, Pos { x: x + dx * nba1, y: y + dy * nba1 }, c) ||
+                check_pos(grd, Pos { x: x + dx * nba1, y: y + dy * nba1 }, CELL_EMPTY)) {
+                break;
+            }
+            nba1 += 1;
+        }
+
+        let mut nba2: i16 = 0;
+        loop {
+            if !(check_pos(grd, Pos { x: x - dx * nba2, y: y - dy * nba2 }, c) ||
+                check_pos(grd, Pos { x: x - dx * nba2, y: y - dy * nba2 }, CELL_EMPTY)) {
+                break;
+            }
+            nba2 += 1;
+        }
+
+        // Without `blocked_wins`, a line between two opponent stones needs room for one more.
+        let opp = if c == CELL_BLACK { CELL_WHITE } else { CELL_BLACK };
+        let blocked = !rules.blocked_wins
+            && check_pos(grd, Pos { x: x + dx * nba1, y: y + dy * nba1 }, opp)
+            && check_pos(grd, Pos { x: x - dx * nba2, y: y - dy * nba2 }, opp);
+        (nba1 + nba2 - 2) as usize >= rules.win_len + blocked as usize
+    }
+
+    fn score_for(f: fn(usize, usize, usize) -> Pos, score: &mut i32, c: i8, grd: &Board, rules: &RuleSet) {
+        let size = grd.size();
+        let mut nba: i32;
+        let mut last_bad_empty: bool;
+
+        for x in 0..(size + 1) {
+            last_bad_empty = false;
+            nba = 0;
+            for y in 0..(size + 1) {
+                let p = f(x, y, size);
+
+                if check_pos(grd, p, c) {
+                    nba += 1;
+                } else if check_pos(grd, p, CELL_EMPTY) {
+                    let mut ds = nba_to_score(nba, rules.win_len);
+                    if nba >= 3 {
+                        if last_bad_empty {
+                            ds *= 9;
+                        }
+                        if !check_can_deploy(grd, p, f(x, y - 1, size), c, rules) {
+                            ds = dead_line_score(ds, nba, rules);
+                        }
+                    }
+                    *score += ds;
+                    nba = 0;
+                    last_bad_empty = true;
+                } else {
+                    let mut ds = nba_to_score(nba, rules.win_len);
+                    if nba >= 3 {
+                        if !last_bad_empty {
+                            ds /= 2;
+                        }
+                        if !check_can_deploy(grd, p, f(x, y - 1, size), c, rules) {
+                            ds = dead_line_score(ds, nba, rules);
+                        }
+                    }
+                    *score += ds;
+                    nba = 0;
+                    last_bad_empty = false;
+                }
+            }
+        }
+    }
+
+    fn b1(x: usize, y: usize, _size: usize) -> Pos {
+        Pos { x: x as i16, y: y as i16 }
+    }
+    fn b2(x: usize, y: usize, _size: usize) -> Pos {
+        Pos { x: y as i16, y: x as i16 }
+    }
+    fn b3(x: usize, y: usize, _size: usize) -> Pos {
+        Pos { x: (x + y) as i16, y: y as i16 }
+    }
+    fn b4(x: usize, y: usize, _size: usize) -> Pos {
+        Pos { x: y as i16, y: (x + y) as i16 }
+    }
+    fn b5(x: usize, y: usize, _size: usize) -> Pos {
+        Pos { x: (x as i16) - (y as i16), y: y as i16 }
+    }
+    fn b6(x: usize, y: usize, size: usize) -> Pos {
+        Pos { x: (size as i16) - 1 - (y as i16), y: (x + y) as i16 }
+    }
+
+    score_for(b1, &mut score, c, grd, rules);
+    score_for(b2, &mut score, c, grd, rules);
+    score_for(b3, &mut score, c, grd, rules);
+    score_for(b4, &mut score, c, grd, rules);
+    score_for(b5, &mut score, c, grd, rules);
+    score_for(b6, &mut score, c, grd, rules);
+
+    score
+}
+
+fn scoring_end(
+    grd: &Board,
+    nb_cap_white: i16,
+    nb_cap_black: i16,
+    player: Player,
+    rules: &RuleSet,
+) -> i32 {
+    let mut score: i32 = match player {
+        Player::White => ((nb_cap_white - nb_cap_black) as i32) * score_cap(rules),
+        Player::Black => ((nb_cap_black - nb_cap_white) as i32) * score_cap(rules),
+    };
+    score += scoring_align(grd, player, rules);
+    score -= scoring_align(grd, next_player(player), rules);
+    if !rules.capture.enabled {
+        return score;
+    }
+
+    let next_nb_cap_max: i16 = {
+        let mut valid = empty_pos(grd);
+        valid = del_dist_1(&valid, grd.size());
+        rules.del_forbidden(grd, &mut valid, player);
+        let lpos = valid_to_pos(&valid);
+
+        let mut next_nb_cap_max: i16 = 0;
+        for p in lpos.iter() {
+            let c: i16 = countcap(grd, *p, player);
+            next_nb_cap_max = next_nb_cap_max.max(c);
+        }
+        next_nb_cap_max
+    };
+    score += (next_nb_cap_max as i32) * (score_cap(rules) / 2);
+
+    score
+}
+
+/// Score of a forced win whose last move is the last one of `line`, played from a node at
+/// `depth`.
+fn vcf_score(depth: i16, line: &[Pos]) -> i32 {
+    SCORE_MAX + (depth as i32 - line.len() as i32) * DEPTH_MALUS
+}
+
+fn score_to_tt(score: i32, depth: i16) -> i32 {
+    if score >= SCORE_WIN_MIN {
+        score - (depth as i32) * DEPTH_MALUS
+    } else if score <= -SCORE_WIN_MIN {
+        score + (depth as i32) * DEPTH_MALUS
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, depth: i16) -> i32 {
+    if score >= SCORE_WIN_MIN {
+        score + (depth as i32) * DEPTH_MALUS
+    } else if score <= -SCORE_WIN_MIN {
+        score - (depth as i32) * DEPTH_MALUS
+    } else {
+        score
+    }
+}
+
+/// State shared by all the nodes of one iteration.
+#[derive(Clone)]
+pub struct SearchCtx {
+    pub tt: Arc<TranspositionTable>,
+    pub stop: Arc<AtomicBool>,
+    pub deadline: Option<Instant>,
+    pub root_depth: i16,
+    pub rules: RuleSet,
+}
+
+impl SearchCtx {
+    fn is_stopped(&self) -> bool {
+        if self.stop.load(Ordering::Relaxed) {
+            return true;
+        }
+        if let Some(d) = self.deadline {
+            if Instant::now() >= d {
+                self.stop.store(true, Ordering::Relaxed);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Moves that made cutoffs earlier in the search, tried first in the nodes that follow.
+#[derive(Clone)]
+pub struct MoveOrder {
+    /// Last two moves that made a cutoff at each ply.
+    killers: Vec<[Option<Pos>; 2]>,
+    /// Cutoffs made by each move of each player, weighted by the depth they were made at.
+    history: Vec<i32>,
+}
+
+impl Default for MoveOrder {
+    fn default() -> Self {
+        MoveOrder::new()
+    }
+}
+
+impl MoveOrder {
+    pub fn new() -> Self {
+        MoveOrder { killers: Vec::new(), history: vec![0; 2 * NB_BITS] }
+    }
+
+    fn history_index(player: Player, p: Pos) -> usize {
+        (player_to_i8(player) as usize - 1) * NB_BITS + BitSet::index(p)
+    }
+
+    /// Ordering score of `p` for `player` at `ply`, added to the static one.
+    fn bonus(&self, ply: usize, player: Player, p: Pos) -> i32 {
+        let killer = self.killers.get(ply).is_some_and(|k| k.contains(&Some(p)));
+        let history = self.history[Self::history_index(player, p)] * (SCORE_KILLER - 1) / HISTORY_MAX;
+        if killer { SCORE_KILLER + history } else { history }
+    }
+
+    fn cutoff(&mut self, ply: usize, player: Player, p: Pos, depth: i16) {
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(p) {
+            killers[1] = killers[0];
+            killers[0] = Some(p);
+        }
+
+        let i = Self::history_index(player, p);
+        self.history[i] += (depth as i32) * (depth as i32);
+        if self.history[i] > HISTORY_MAX {
+            for h in self.history.iter_mut() {
+                *h /= 2;
+            }
+        }
+    }
+}
+
+/// Score of `grd` for `player`, to move, and its best move, searched `depth` plies deep and past
+/// the horizon on fours and captures. `ply` counts the moves from the root, which `depth` does
+/// not once forced replies are extended.
+#[allow(clippy::too_many_arguments)]
+pub fn nega_max(
+    grd: &Board,
+    nb_cap_white: i16,
+    nb_cap_black: i16,
+    depth: i16,
+    ply: usize,
+    alpha: i32,
+    beta: i32,
+    player: Player,
+    last_move: Option<Pos>,
+    prev_move: Option<Pos>,
+    ctx: &SearchCtx,
+    order: &mut MoveOrder,
+    pv: &mut Vec<Pos>,
+) -> (Pos, i32) {
+    pv.clear();
+    // The root goes on to its moves, so that a search stopped at once still has one to play:
+    // the first of its ordering.
+    if ply > 0 && ctx.is_stopped() {
+        return (Pos { x: 0, y: 0 }, 0);
+    }
+
+    let tt = &ctx.tt;
+    let rules = &ctx.rules;
+    let mut alpha_mut = alpha;
+    let mut to_find: (Pos, i32) = (grd.center(), -INF);
+
+    let score_end: i32 = SCORE_MAX + (depth as i32) * DEPTH_MALUS;
+    if rules.is_cap_win(nb_cap_black) {
+        if player == Player::Black {
+            return (Pos { x: 0, y: 0 }, score_end);
+        } else {
+            return (Pos { x: 0, y: 0 }, -score_end);
+        }
+    }
+    if rules.is_cap_win(nb_cap_white) {
+        if player == Player::White {
+            return (Pos { x: 0, y: 0 }, score_end);
+        } else {
+            return (Pos { x: 0, y: 0 }, -score_end);
+        }
+    }
+    let end = match last_move {
+        // Second stone of a two-stone turn, only the player who laid it can have won.
+        Some(lm) if grd.get(lm) == player_to_i8(player) => Some(player).filter(|&p| rules.has_five_at(grd, lm, p)),
+        Some(lm) => check_end_local(grd, nb_cap_white, nb_cap_black, player, lm, prev_move, rules),
+        None => check_end_grd(grd, nb_cap_white, nb_cap_black, player, None, rules),
+    };
+    if let Some(p) = end {
+        if p == player {
+            return (Pos { x: 0, y: 0 }, score_end);
+        } else {
+            return (Pos { x: 0, y: 0 }, -score_end);
+        }
+    }
+
+    let key = zobrist::position_key(grd, nb_cap_white, nb_cap_black, player);
+    let mut tt_move: Option<Pos> = None;
+    if let Some(entry) = tt.probe(key) {
+        tt_move = entry.best_move;
+        // Without a move the entry can only answer for a leaf, the caller may need one.
+        if entry.depth >= depth && (depth <= 0 || entry.best_move.is_some()) {
+            let score = score_from_tt(entry.score, depth);
+            let pos = entry.best_move.unwrap_or(Pos { x: 0, y: 0 });
+            match entry.bound {
+                // A node with an open window is on the line the search answers with, which the
+                // table only keeps the first move of.
+                Bound::Exact if beta - alpha > 1 => (),
+                Bound::Exact => {
+                    pv.extend(entry.best_move);
+                    return (pos, score);
+                }
+                Bound::Lower if score >= beta => return (pos, score),
+                Bound::Upper if score <= alpha => return (pos, score),
+                _ => (),
+            }
+        }
+    }
+
+    // Past the horizon the player to move may stand on the static score, or play on its fours
+    // and captures until the position is quiet. The fours and their forced replies make up
+    // the forced wins by fours of up to `DEPTH_QUIESCENCE` fours, which no separate search
+    // looks for at the leaves.
+    let quiet = depth <= 0;
+    if quiet && (rules.stones_per_turn > 1 || depth <= -DEPTH_QUIESCENCE) {
+        let score = scoring_end(grd, nb_cap_white, nb_cap_black, player, rules);
+        tt.store(key, TTEntry { depth, bound: Bound::Exact, score: score_to_tt(score, depth), best_move: None });
+        return (Pos { x: 0, y: 0 }, score);
+    }
+
+    // Moves the search is limited to, when the position is tactical or past the horizon.
+    let mut tactical: Option<BitSet> = None;
+    let mut forced = false;
+    let mut stand = -INF;
+    // A five of the opponent that a capture may still break: breaking it is the only move,
+    // even a five of the player's own would come too late.
+    let breaks = match last_move {
+        Some(lm) if rules.capture.can_break() && rules.has_five_at(grd, lm, next_player(player)) => {
+            Some(five_breaks(grd, nb_cap_white, nb_cap_black, player, lm, rules))
+        }
+        _ => None,
+    };
+    if let Some(todo) = breaks {
+        forced = true;
+        tactical = Some(todo);
+    } else if rules.stones_per_turn == 1 {
+        if let Some(p) = vcf::win_moves(grd, nb_cap_white, nb_cap_black, player, rules).iter().map(BitSet::pos).next() {
+            pv.push(p);
+            return (p, SCORE_MAX + (depth as i32 - 1) * DEPTH_MALUS);
+        }
+        let threats = vcf::win_moves(grd, nb_cap_white, nb_cap_black, next_player(player), rules);
+        if !threats.is_empty() {
+            // Blocking the four, or capturing stones out of it, are the only replies.
+            forced = true;
+            let mut todo = threats;
+            if rules.capture.enabled {
+                todo |= capture_moves(grd, player);
+            }
+            tactical = Some(todo);
+        } else if quiet {
+            stand = scoring_end(grd, nb_cap_white, nb_cap_black, player, rules);
+            if stand >= beta {
+                return (Pos { x: 0, y: 0 }, stand);
+            }
+            let mut todo = vcf::four_moves(grd, player, rules);
+            if rules.capture.enabled && depth > -DEPTH_QUIESCENCE_CAPTURE {
+                todo |= capture_moves(grd, player);
+            }
+            tactical = Some(todo);
+        }
+    }
+    alpha_mut = alpha_mut.max(stand);
+
+    let lpos_score: Vec<(Pos, i32)> = {
+        let mut valid = tactical.map_or_else(BitSet::new, |todo| todo & empty_pos(grd));
+        rules.del_forbidden(grd, &mut valid, player);
+        if tactical.is_some() && !forced && valid.is_empty() {
+            return (Pos { x: 0, y: 0 }, stand);
+        }
+        if valid.is_empty() {
+            // Not tactical, or no way to stop the four: any move next to the stones.
+            valid = del_dist_1(&empty_pos(grd), grd.size());
+            rules.del_forbidden(grd, &mut valid, player);
+        }
+        if valid.is_empty() {
+            // Nothing allowed next to the stones, as for the second black stone of a Pro
+            // opening: the closest allowed cells instead.
+            let mut allowed = empty_pos(grd);
+            rules.del_forbidden(grd, &mut allowed, player);
+            let mut near = grd.occupied();
+            for _ in 0..grd.size() {
+                near = near.dilate();
+                valid = allowed & near;
+                if !valid.is_empty() {
+                    break;
+                }
+            }
+        }
+        if valid.is_empty() {
+            tt.store(key, TTEntry { depth, bound: Bound::Exact, score: SCORE_DRAW, best_move: None });
+            return (Pos { x: 0, y: 0 }, SCORE_DRAW);
+        }
+        let lpos = valid_to_pos(&valid);
+
+        let mut lpos_score: Vec<(Pos, i32)> = Vec::new();
+        for p in lpos.iter() {
+            let score = scoring_ordoring(grd, *p, player, nb_cap_white, nb_cap_black, rules);
+            lpos_score.push((*p, score.saturating_add(order.bonus(ply, player, *p))))
+        }
+        lpos_score.sort_by_key(|k| k.1);
+        lpos_score.reverse();
+
+        if let Some(m) = tt_move {
+            if valid.contains(m) {
+                lpos_score.retain(|k| k.0 != m);
+                lpos_score.insert(0, (m, INF));
+            }
+        }
+
+        lpos_score
+    };
+
+    // Two-stone turns come without captures, so the stones on the board count the moves.
+    let next = if rules.stones_per_turn == 1 {
+        next_player(player)
+    } else {
+        rules.player_of_move(grd.occupied().count() as usize + 1)
+    };
+
+    let mut cp: Board;
+    let mut child_pv = Vec::new();
+    for (i, (pos, _)) in lpos_score.iter().enumerate() {
+        cp = *grd;
+        cp.set(*pos, player_to_i8(player));
+        let cap = if rules.capture.enabled { delcap(&mut cp, *pos, player) } else { 0 };
+
+        // The same player laying its second stone keeps the window and the score.
+        let mut child = |a: i32, b: i32| {
+            let (a, b, sign) = if next == player { (a, b, 1) } else { (-b, -a, -1) };
+            let (_, s) = nega_max(
+                &cp,
+                if player == Player::White { nb_cap_white + cap } else { nb_cap_white },
+                if player == Player::Black { nb_cap_black + cap } else { nb_cap_black },
+                // A forced reply is searched as deep as the move it answers.
+                if forced { depth } else { depth - 1 },
+                ply + 1,
+                a,
+                b,
+                next,
+                Some(*pos),
+                last_move,
+                ctx,
+                order,
+                &mut child_pv,
+            );
+            sign * s
+        };
+        // Past the first move, a null window only proves the move no better than the best
+        // one, and the move is searched again with the full window when it is.
+        let ss = if i == 0 {
+            child(alpha_mut, beta)
+        } else {
+            let s = child(alpha_mut, alpha_mut + 1);
+            if s > alpha_mut && s < beta { child(alpha_mut, beta) } else { s }
+        };
+        if ss > to_find.1 {
+            to_find = (*pos, ss);
+            pv.clear();
+            pv.push(*pos);
+            pv.extend_from_slice(&child_pv);
+        }
+        alpha_mut = alpha_mut.max(to_find.1);
+        if alpha_mut >= beta || to_find.1 > SCORE_BREAK {
+            if !quiet {
+                order.cutoff(ply, player, *pos, depth);
+            }
+            break;
+        }
+    }
+    if stand > to_find.1 {
+        to_find = (Pos { x: 0, y: 0 }, stand);
+        pv.clear();
+    }
+
+    if ctx.is_stopped() {
+        return to_find;
+    }
+
+    let bound = if to_find.1 <= alpha {
+        Bound::Upper
+    } else if to_find.1 >= beta || to_find.1 > SCORE_BREAK {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    let best_move = pv.first().copied();
+    tt.store(key, TTEntry { depth, bound, score: score_to_tt(to_find.1, depth), best_move });
+
+    to_find
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SolverConfig {
+    pub tt_size_mb: usize,
+    /// Time allowed for one move, `None` to always search to `max_depth`.
+    pub time_limit: Option<Duration>,
+    pub max_depth: i16,
+    /// Search threads, the calling one included. With one thread the search is sequential.
+    pub threads: usize,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            tt_size_mb: DEFAULT_TT_SIZE_MB,
+            time_limit: Some(Duration::from_millis(DEFAULT_TIME_LIMIT_MS)),
+            max_depth: MAX_DEPTH,
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SearchResult {
+    pub best_move: Pos,
+    /// Line expected from `best_move` on, both players' moves in turn.
+    pub pv: Vec<Pos>,
+    pub score: i32,
+    /// Depth of the last completed iteration.
+    pub depth: i16,
+    pub time: Duration,
+}
+
+/// What the solver does on its turn: lay a stone or answer a swap.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Decision {
+    Play(SearchResult),
+    /// The swap answer, with the search it was based on.
+    Choose(SwapChoice, SearchResult),
+}
+
+/// Time by which a search started at `start` has to end, a margin before its `time_limit`.
+fn search_deadline(start: Instant, time_limit: Option<Duration>) -> Option<Instant> {
+    time_limit.map(|t| start + t - t / TIME_MARGIN_DIV)
+}
+
+/// Deepens the search one ply at a time from `first_depth` until `max_depth`, a forced result,
+/// the deadline or `stop`, and answers with the last iteration that completed, or with the
+/// best move of the first one at depth 0 when none did.
+fn iterative_deepening(
+    state: &GameState,
+    tt: &Arc<TranspositionTable>,
+    stop: &Arc<AtomicBool>,
+    start: Instant,
+    time_limit: Option<Duration>,
+    first_depth: i16,
+    max_depth: i16,
+) -> SearchResult {
+    let mut result = SearchResult {
+        best_move: state.board().center(),
+        pv: Vec::new(),
+        score: 0,
+        depth: 0,
+        time: Duration::from_millis(0),
+    };
+
+    let mut order = MoveOrder::new();
+    'deepening: for depth in first_depth..=max_depth {
+        let ctx = SearchCtx {
+            tt: Arc::clone(tt),
+            stop: Arc::clone(stop),
+            deadline: search_deadline(start, time_limit),
+            root_depth: depth,
+            rules: *state.rules(),
+        };
+
+        // A window around the score of the last iteration, widened each time the score falls
+        // out of it.
+        let mut delta = ASPIRATION_DELTA;
+        let (mut alpha, mut beta) = if result.depth > 0 && result.score.abs() < SCORE_WIN_MIN {
+            (result.score - delta, result.score + delta)
+        } else {
+            (-INF, INF)
+        };
+        let mut pv = Vec::new();
+        let (best_move, score) = loop {
+            let (best_move, score) = nega_max(
+                state.board(),
+                state.nb_cap(Player::White),
+                state.nb_cap(Player::Black),
+                depth,
+                0,
+                alpha,
+                beta,
+                state.player_turn(),
+                state.last_move(),
+                state.prev_move(),
+                &ctx,
+                &mut order,
+                &mut pv,
+            );
+            if ctx.is_stopped() {
+                if result.depth == 0 {
+                    result = SearchResult { best_move, pv, score, depth: 0, time: start.elapsed() };
+                }
+                break 'deepening;
+            }
+            delta = delta.saturating_mul(ASPIRATION_GROWTH);
+            if score <= alpha && alpha > -INF {
+                alpha = if delta < SCORE_WIN_MIN { alpha - delta } else { -INF };
+            } else if score >= beta && beta < INF {
+                beta = if delta < SCORE_WIN_MIN { beta + delta } else { INF };
+            } else {
+                break (best_move, score);
+            }
+        };
+        result = SearchResult { best_move, pv, score, depth, time: start.elapsed() };
+
+        if score.abs() >= SCORE_WIN_MIN {
+            break;
+        }
+        // The next iteration takes longer than all the previous ones.
+        if let Some(t) = time_limit {
+            if start.elapsed() * 2 > t {
+                break;
+            }
+        }
+    }
+
+    result
+}
+
+/// One search for a helper thread.
+struct Job {
+    state: GameState,
+    tt: Arc<TranspositionTable>,
+    stop: Arc<AtomicBool>,
+    start: Instant,
+    time_limit: Option<Duration>,
+    first_depth: i16,
+    max_depth: i16,
+    done: mpsc::Sender<()>,
+}
+
+/// Helper threads kept alive between searches, each waiting for its next job.
+struct WorkerPool {
+    workers: Vec<(mpsc::Sender<Job>, thread::JoinHandle<()>)>,
+}
+
+impl WorkerPool {
+    fn new(nb_worker: usize) -> Self {
+        let workers = (0..nb_worker)
+            .map(|_| {
+                let (tx, rx) = mpsc::channel::<Job>();
+                let handle = thread::spawn(move || {
+                    for job in rx.iter() {
+                        iterative_deepening(
+                            &job.state,
+                            &job.tt,
+                            &job.stop,
+                            job.start,
+                            job.time_limit,
+                            job.first_depth,
+                            job.max_depth,
+                        );
+                        let _ = job.done.send(());
+                    }
+                });
+                (tx, handle)
+            })
+            .collect();
+        WorkerPool { workers }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        for (tx, handle) in self.workers.drain(..) {
+            drop(tx);
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Iterative deepening search, with a transposition table kept from one move to the next.
+///
+/// With more than one thread the search is a Lazy SMP: the helpers run the same iterative
+/// deepening one or two plies ahead of the main thread and only share their work through the
+/// transposition table. The move played is always the main thread's.
+pub struct Solver {
+    config: SolverConfig,
+    tt: Arc<TranspositionTable>,
+    stop: Arc<AtomicBool>,
+    pool: WorkerPool,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new(SolverConfig::default())
+    }
+}
+
+impl Solver {
+    pub fn new(config: SolverConfig) -> Self {
+        Solver {
+            config,
+            tt: Arc::new(TranspositionTable::new(config.tt_size_mb)),
+            stop: Arc::new(AtomicBool::new(false)),
+            pool: WorkerPool::new(config.threads.max(1) - 1),
+        }
+    }
+
+    pub fn config(&self) -> &SolverConfig {
+        &self.config
+    }
+
+    pub fn set_time_limit(&mut self, time_limit: Option<Duration>) {
+        self.config.time_limit = time_limit;
+    }
+
+    pub fn clear(&self) {
+        self.tt.clear();
+    }
+
+    /// Flag that ends the running search from another thread, which then answers with its
+    /// last completed iteration. Set before a search starts, it ends that one at once. Each
+    /// search clears it when it returns.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Makes `stop` the flag of the next searches, for a caller that gives each one its own.
+    pub fn set_stop_handle(&mut self, stop: Arc<AtomicBool>) {
+        self.stop = stop;
+    }
+
+    /// Forced win of the player to move by continuous fours, or else by continuous threats,
+    /// its moves alternating with the replies. Gives up when the search is stopped or the
+    /// `deadline` passes.
+    pub fn find_forced_win(&self, state: &GameState, deadline: Option<Instant>) -> Option<Vec<Pos>> {
+        let player = state.player_turn();
+        vcf::find_vcf(state, MAX_DEPTH_VCF, Limits::vcf().until(&self.stop, deadline))
+            .or_else(|| vcf::find_vct(state, player, MAX_DEPTH_VCT, Limits::vct().until(&self.stop, deadline)))
+    }
+
+    /// Moves stopping the win by continuous threats the opponent would have if it were to
+    /// move, among the cells it would play, as long as the time allows. Empty when it has no
+    /// such win, when none of them stops it, or when it has a five to break first.
+    fn defences(&self, state: &GameState, deadline: Option<Instant>) -> Vec<Pos> {
+        if state.pending_five().is_some() {
+            return Vec::new();
+        }
+        let player = state.player_turn();
+        let opponent = next_player(player);
+        let limits = Limits::vct().until(&self.stop, deadline);
+        let threat = match vcf::find_vct(state, opponent, MAX_DEPTH_VCT, limits) {
+            Some(line) => line,
+            None => return Vec::new(),
+        };
+
+        // A threat search cut short finds nothing, which does not make the move a defence.
+        let out_of_time = || self.stop.load(Ordering::Relaxed) || deadline.is_some_and(|d| Instant::now() >= d);
+        let mut todo = Vec::new();
+        for &p in threat.iter().step_by(2) {
+            if !state.is_legal(p) {
+                continue;
+            }
+            let mut cp = state.clone();
+            let _ = cp.play(p);
+            let stops = cp.end() == Some(Some(player)) || vcf::find_vct(&cp, opponent, MAX_DEPTH_VCT, limits).is_none();
+            if out_of_time() {
+                break;
+            }
+            if stops {
+                todo.push(p);
+            }
+        }
+        todo
+    }
+
+    /// Score the search left in the table for the player to move in `state` playing `p`.
+    fn table_score(&self, state: &GameState, p: Pos) -> Option<i32> {
+        let mut cp = state.clone();
+        let _ = cp.play(p);
+        let key = zobrist::position_key(cp.board(), cp.nb_cap(Player::White), cp.nb_cap(Player::Black), cp.player_turn());
+        self.tt.probe(key).map(|e| -e.score)
+    }
+
+    /// Move of `defences` with the best score the search left in the table.
+    fn best_defence(&self, state: &GameState, defences: &[Pos]) -> Pos {
+        let score = |p: Pos| self.table_score(state, p).unwrap_or(-INF);
+        defences.iter().cloned().max_by_key(|&p| score(p)).unwrap_or_else(|| state.board().center())
+    }
+
+    /// Result of the search started at `start` with `defence` played, searched again up to
+    /// `max_depth` for its own line and score. Past the deadline it is left at depth 0, with
+    /// the score of the table.
+    fn search_defence(&self, state: &GameState, defence: Pos, start: Instant, max_depth: i16) -> SearchResult {
+        let mut cp = state.clone();
+        let _ = cp.play(defence);
+        let (score, line, depth) = match cp.end() {
+            Some(None) => (SCORE_DRAW, Vec::new(), 1),
+            Some(Some(p)) if p == state.player_turn() => (SCORE_MAX, Vec::new(), 1),
+            Some(Some(_)) => (-SCORE_MAX, Vec::new(), 1),
+            None => {
+                let res = iterative_deepening(&cp, &self.tt, &self.stop, start, self.config.time_limit, 1, max_depth);
+                if res.depth > 0 {
+                    (-res.score, res.pv, res.depth + 1)
+                } else {
+                    (self.table_score(state, defence).unwrap_or(0), Vec::new(), 0)
+                }
+            }
+        };
+        let mut pv = vec![defence];
+        pv.extend(line);
+        SearchResult { best_move: defence, pv, score, depth, time: start.elapsed() }
+    }
+
+    pub fn best_move(&self, state: &GameState) -> Pos {
+        self.search(state).best_move
+    }
+
+    /// Next action of the seat to move, opening included: balanced stones while laying an
+    /// opening for the opponent to choose from, the best move once the colours are known.
+    pub fn decide(&self, state: &GameState) -> Decision {
+        match state.opening_step() {
+            OpeningStep::Choose(_, choices) => {
+                let (choice, res) = self.choose_swap(state, choices);
+                Decision::Choose(choice, res)
+            }
+            OpeningStep::Place(_) => Decision::Play(self.opening_move(state)),
+            OpeningStep::Done => Decision::Play(self.search(state)),
+        }
+    }
+
+    /// Picks the colour with the better outlook, or two more stones when the position is
+    /// even and `choices` allow it.
+    pub fn choose_swap(&self, state: &GameState, choices: &[SwapChoice]) -> (SwapChoice, SearchResult) {
+        let res = self.search(state);
+        let score_white = match state.player_turn() {
+            Player::White => res.score,
+            Player::Black => -res.score,
+        };
+
+        let choice = if choices.contains(&SwapChoice::PlaceTwo) && score_white.abs() < SCORE_BALANCED {
+            SwapChoice::PlaceTwo
+        } else if score_white >= 0 {
+            SwapChoice::White
+        } else {
+            SwapChoice::Black
+        };
+        (choice, res)
+    }
+
+    /// Stone that leaves the position the closest to even, for an opening the opponent will
+    /// pick a colour from.
+    pub fn opening_move(&self, state: &GameState) -> SearchResult {
+        let result = self.balance_opening(state);
+        self.stop.store(false, Ordering::Relaxed);
+        result
+    }
+
+    fn balance_opening(&self, state: &GameState) -> SearchResult {
+        let now = Instant::now();
+
+        let grd = state.board();
+        let mut result = SearchResult {
+            best_move: grd.center(),
+            pv: vec![grd.center()],
+            score: 0,
+            depth: 0,
+            time: Duration::from_millis(0),
+        };
+        if grd.occupied().is_empty() {
+            result.time = now.elapsed();
+            return result;
+        }
+
+        let ctx = SearchCtx {
+            tt: Arc::clone(&self.tt),
+            stop: Arc::clone(&self.stop),
+            deadline: search_deadline(now, self.config.time_limit),
+            root_depth: DEPTH_OPENING,
+            rules: *state.rules(),
+        };
+        let mut best = INF;
+        let mut order = MoveOrder::new();
+        for p in valid_to_pos(&del_dist_1(&grd.empty(), grd.size())) {
+            if !state.is_legal(p) {
+                continue;
+            }
+            let mut cp = state.clone();
+            let _ = cp.play(p);
+            let mut pv = Vec::new();
+            let (_, score) = nega_max(
+                cp.board(),
+                cp.nb_cap(Player::White),
+                cp.nb_cap(Player::Black),
+                DEPTH_OPENING,
+                1,
+                -INF,
+                INF,
+                cp.player_turn(),
+                cp.last_move(),
+                cp.prev_move(),
+                &ctx,
+                &mut order,
+                &mut pv,
+            );
+            if ctx.is_stopped() {
+                if best == INF {
+                    result.best_move = p;
+                    result.pv = vec![p];
+                }
+                break;
+            }
+            if score.abs() < best {
+                best = score.abs();
+                pv.insert(0, p);
+                result = SearchResult { best_move: p, pv, score: -score, depth: DEPTH_OPENING, time: now.elapsed() };
+            }
+        }
+
+        result.time = now.elapsed();
+        result
+    }
+
+    pub fn search(&self, state: &GameState) -> SearchResult {
+        let result = self.search_position(state);
+        self.stop.store(false, Ordering::Relaxed);
+        result
+    }
+
+    fn search_position(&self, state: &GameState) -> SearchResult {
+        let now = Instant::now();
+
+        if let Some(end) = state.end() {
+            let score = match end {
+                None => SCORE_DRAW,
+                Some(p) if p == state.player_turn() => SCORE_MAX,
+                Some(_) => -SCORE_MAX,
+            };
+            return SearchResult {
+                best_move: state.board().center(),
+                pv: Vec::new(),
+                score,
+                depth: 0,
+                time: now.elapsed(),
+            };
+        }
+
+        if state.board().occupied().is_empty() {
+            return SearchResult {
+                best_move: state.board().center(),
+                pv: vec![state.board().center()],
+                score: 0,
+                depth: 0,
+                time: now.elapsed(),
+            };
+        }
+        let threat_deadline = self.config.time_limit.map(|t| now + t / THREAT_TIME_DIV);
+        if let Some(line) = self.find_forced_win(state, threat_deadline) {
+            // Found outside of the iterations, at no search depth.
+            return SearchResult {
+                best_move: line[0],
+                score: vcf_score(0, &line),
+                depth: 0,
+                pv: line,
+                time: now.elapsed(),
+            };
+        }
+
+        let defences = self.defences(state, threat_deadline);
+
+        let (done_tx, done_rx) = mpsc::channel();
+        let mut nb_job = 0;
+        for (i, (tx, _)) in self.pool.workers.iter().enumerate() {
+            let job = Job {
+                state: state.clone(),
+                tt: Arc::clone(&self.tt),
+                stop: Arc::clone(&self.stop),
+                start: now,
+                time_limit: self.config.time_limit,
+                first_depth: (2 + (i % 2) as i16).min(self.config.max_depth),
+                max_depth: self.config.max_depth,
+                done: done_tx.clone(),
+            };
+            if tx.send(job).is_ok() {
+                nb_job += 1;
+            }
+        }
+
+        let mut result = iterative_deepening(
+            state,
+            &self.tt,
+            &self.stop,
+            now,
+            self.config.time_limit,
+            1,
+            self.config.max_depth,
+        );
+
+        if !defences.is_empty() && !defences.contains(&result.best_move) && result.score < SCORE_WIN_MIN {
+            let defence = self.best_defence(state, &defences);
+            result = self.search_defence(state, defence, now, (result.depth - 1).max(1));
+        }
+
+        self.stop.store(true, Ordering::Relaxed);
+        for _ in 0..nb_job {
+            let _ = done_rx.recv();
+        }
+        result.time = now.elapsed();
+        result
+    }
+}
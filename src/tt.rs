@@ -0,0 +1,105 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::bitboard::BitSet;
+use crate::board::Pos;
+
+pub const DEFAULT_TT_SIZE_MB: usize = 64;
+
+const NO_MOVE: u64 = 0x3ff;
+const FLAG_USED: u64 = 1 << 63;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TTEntry {
+    pub depth: i16,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<Pos>,
+}
+
+impl TTEntry {
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let mv = match self.best_move {
+            Some(p) => BitSet::index(p) as u64,
+            None => NO_MOVE,
+        };
+        (self.score as u32 as u64)
+            | ((self.depth as u8 as u64) << 32)
+            | (bound << 40)
+            | (mv << 42)
+            | FLAG_USED
+    }
+
+    fn unpack(data: u64) -> Self {
+        let mv = (data >> 42) & NO_MOVE;
+        TTEntry {
+            score: data as u32 as i32,
+            depth: (data >> 32) as u8 as i8 as i16,
+            bound: match (data >> 40) & 3 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+            best_move: if mv == NO_MOVE { None } else { Some(BitSet::pos(mv as usize)) },
+        }
+    }
+}
+
+/// Fixed size hash table of search results, indexed by Zobrist key.
+///
+/// Each slot holds the packed entry and the key xored with it, so that a slot written by
+/// two threads at once is read back as a miss instead of a wrong entry.
+pub struct TranspositionTable {
+    slots: Vec<(AtomicU64, AtomicU64)>,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        let nb_slot = (size_mb.max(1) << 20) / std::mem::size_of::<(AtomicU64, AtomicU64)>();
+        let mut slots = Vec::with_capacity(nb_slot);
+        slots.resize_with(nb_slot, || (AtomicU64::new(0), AtomicU64::new(0)));
+        TranspositionTable { slots }
+    }
+
+    fn slot(&self, key: u64) -> &(AtomicU64, AtomicU64) {
+        &self.slots[(key % self.slots.len() as u64) as usize]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TTEntry> {
+        let (check, data) = self.slot(key);
+        let data = data.load(Ordering::Relaxed);
+        if data == 0 || check.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        Some(TTEntry::unpack(data))
+    }
+
+    /// Keeps the deepest entry of a slot, unless the new one is for another position.
+    pub fn store(&self, key: u64, entry: TTEntry) {
+        let (check, data) = self.slot(key);
+        let old = data.load(Ordering::Relaxed);
+        if old != 0 && check.load(Ordering::Relaxed) ^ old == key && TTEntry::unpack(old).depth > entry.depth {
+            return;
+        }
+        let new = entry.pack();
+        check.store(key ^ new, Ordering::Relaxed);
+        data.store(new, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for (check, data) in self.slots.iter() {
+            check.store(0, Ordering::Relaxed);
+            data.store(0, Ordering::Relaxed);
+        }
+    }
+}
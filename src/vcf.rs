@@ -0,0 +1,503 @@
+//! Threat-space search. Victory by continuous fours: the attacker only plays moves that
+//! threaten to win at once, so that the defender's replies are forced, until a threat cannot be
+//! answered. Victory by continuous threats also lets it play open threes, which threaten a four
+//! that cannot be answered, and leave the defender a few more replies to look at.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use crate::bitboard::{BitSet, LINE_SHIFT};
+use crate::board::*;
+use crate::game::GameState;
+use crate::opening::OpeningStep;
+use crate::rules::RuleSet;
+
+/// Most fours of the attacker in one sequence.
+pub const MAX_DEPTH_VCF: usize = 12;
+/// Most positions looked at by one search.
+pub const MAX_NODE_VCF: usize = 20000;
+/// Most threats of the attacker in one sequence of threes and fours.
+pub const MAX_DEPTH_VCT: usize = 6;
+/// Most positions looked at by one search with threes.
+pub const MAX_NODE_VCT: usize = 4000;
+
+/// Bounds of one threat search: the positions it may look at, and the stop flag and deadline
+/// of the search it is part of.
+#[derive(Clone, Copy)]
+pub struct Limits<'a> {
+    pub max_node: usize,
+    pub stop: Option<&'a AtomicBool>,
+    pub deadline: Option<Instant>,
+}
+
+impl<'a> Limits<'a> {
+    /// Positions a search by fours may look at.
+    pub fn vcf() -> Self {
+        Limits { max_node: MAX_NODE_VCF, stop: None, deadline: None }
+    }
+
+    /// Positions a search by threats may look at.
+    pub fn vct() -> Self {
+        Limits { max_node: MAX_NODE_VCT, stop: None, deadline: None }
+    }
+
+    /// Also ends the search once `stop` is set or `deadline` has passed.
+    pub fn until(self, stop: &'a AtomicBool, deadline: Option<Instant>) -> Self {
+        Limits { stop: Some(stop), deadline, ..self }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+    grd: Board,
+    nb_cap_white: i16,
+    nb_cap_black: i16,
+}
+
+impl Node {
+    fn nb_cap(&self, player: Player) -> i16 {
+        match player {
+            Player::White => self.nb_cap_white,
+            Player::Black => self.nb_cap_black,
+        }
+    }
+
+    fn play(&self, p: Pos, player: Player, rules: &RuleSet) -> Node {
+        let mut next = *self;
+        next.grd.set(p, player_to_i8(player));
+        if rules.capture.enabled {
+            let cap = delcap(&mut next.grd, p, player);
+            match player {
+                Player::White => next.nb_cap_white += cap,
+                Player::Black => next.nb_cap_black += cap,
+            }
+        }
+        next
+    }
+}
+
+/// Empty cells of the windows of `win_len` cells through `s` holding `nb_stone` stones of
+/// `player` and nothing else.
+fn window_cells_at(grd: &Board, s: Pos, player: Player, nb_stone: usize, win_len: usize) -> BitSet {
+    let c = player_to_i8(player);
+    let mut todo = BitSet::new();
+    for i in 0..(NB_DIR / 2) {
+        let (dx, dy) = ALL_DIR[i * 2];
+        for start in -(win_len as i16 - 1)..=0 {
+            let mut nb = 0;
+            let mut empty = BitSet::new();
+            for k in start..start + win_len as i16 {
+                let q = Pos { x: s.x + dx * k, y: s.y + dy * k };
+                if check_pos(grd, q, c) {
+                    nb += 1;
+                } else if check_pos(grd, q, CELL_EMPTY) {
+                    empty.insert(BitSet::index(q));
+                } else {
+                    nb = 0;
+                    break;
+                }
+            }
+            if nb == nb_stone {
+                todo |= empty;
+            }
+        }
+    }
+    todo
+}
+
+/// `window_cells_at` for all the stones of `player`, `nb_stone` being at least one. The windows
+/// are counted along each line direction at once, by their first cell.
+fn window_cells(grd: &Board, player: Player, nb_stone: usize, win_len: usize) -> BitSet {
+    let own = grd.stones(player_to_i8(player));
+    let free = grd.empty();
+    let mut todo = BitSet::new();
+
+    for &shift in LINE_SHIFT.iter() {
+        // Windows with no opponent stone nor cell off the board, and their number of stones
+        // of `player` in binary.
+        let mut open = own | free;
+        let mut count = [own, BitSet::new(), BitSet::new()];
+        let (mut own_k, mut free_k) = (own, free);
+        for _ in 1..win_len {
+            own_k = own_k.shr(shift);
+            free_k = free_k.shr(shift);
+            open &= own_k | free_k;
+            let mut carry = own_k;
+            for bit in count.iter_mut() {
+                let next = *bit & carry;
+                *bit = (*bit | carry).and_not(&next);
+                carry = next;
+            }
+        }
+
+        let mut starts = open;
+        for (k, bit) in count.iter().enumerate() {
+            starts = if (nb_stone >> k) & 1 == 1 { starts & *bit } else { starts.and_not(bit) };
+        }
+        for _ in 0..win_len {
+            todo |= starts & free;
+            starts = starts.shl(shift);
+        }
+    }
+    todo
+}
+
+/// Cells among `lines`, where a stone would complete a line, on which `player` wins at once by
+/// a line that cannot be broken, and cells where it reaches the capture win.
+fn win_cells(node: &Node, lines: BitSet, player: Player, rules: &RuleSet) -> BitSet {
+    let grd = &node.grd;
+    let mut todo = BitSet::new();
+
+    for i in lines.iter() {
+        let p = BitSet::pos(i);
+        let next = node.play(p, player, rules);
+        if rules.has_five_at(&next.grd, p, player)
+            && (!rules.capture.can_break()
+                || five_breaks(&next.grd, next.nb_cap_white, next.nb_cap_black, next_player(player), p, rules).is_empty()) {
+            todo.insert(i);
+        }
+    }
+    if rules.capture.enabled {
+        for i in capture_moves(grd, player).iter() {
+            if rules.is_cap_win(node.nb_cap(player) + countcap(grd, BitSet::pos(i), player)) {
+                todo.insert(i);
+            }
+        }
+    }
+
+    rules.del_forbidden(grd, &mut todo, player);
+    todo
+}
+
+/// Cells where `player` wins at once.
+fn win_points(node: &Node, player: Player, rules: &RuleSet) -> BitSet {
+    let lines = window_cells(&node.grd, player, rules.win_len - 1, rules.win_len);
+    win_cells(node, lines, player, rules)
+}
+
+/// Cells where `player` wins at once, when its only lines that may be fours cross `s`.
+fn win_points_at(node: &Node, s: Pos, player: Player, rules: &RuleSet) -> BitSet {
+    let lines = if check_pos(&node.grd, s, player_to_i8(player)) {
+        window_cells_at(&node.grd, s, player, rules.win_len - 1, rules.win_len)
+    } else {
+        BitSet::new()
+    };
+    win_cells(node, lines, player, rules)
+}
+
+struct ThreatSearch<'a> {
+    rules: &'a RuleSet,
+    attacker: Player,
+    /// Whether open threes count as threats, beside fours.
+    threes: bool,
+    nb_node: usize,
+    limits: Limits<'a>,
+    /// Whether the limits were reached, every search from then on failing.
+    exhausted: bool,
+}
+
+impl<'a> ThreatSearch<'a> {
+    fn new(rules: &'a RuleSet, attacker: Player, threes: bool, limits: Limits<'a>) -> Self {
+        ThreatSearch { rules, attacker, threes, nb_node: 0, limits, exhausted: false }
+    }
+
+    /// Counts one more position, and tells whether the search has to end. The clock is read
+    /// at every position, each one costing far more than that with the replies it tries.
+    fn count_node(&mut self) -> bool {
+        self.nb_node += 1;
+        let stop = self.limits.stop.is_some_and(|s| s.load(Ordering::Relaxed));
+        let late = self.limits.deadline.is_some_and(|d| Instant::now() >= d);
+        self.exhausted = self.nb_node > self.limits.max_node || stop || late;
+        self.exhausted
+    }
+
+    /// Whether the four the attacker just made with `t` wins whatever the defender answers.
+    fn four_wins(&self, node: &Node, t: Pos) -> bool {
+        let rules = self.rules;
+        let (attacker, defender) = (self.attacker, next_player(self.attacker));
+        let threats = win_points_at(node, t, attacker, rules);
+        if threats.is_empty() || (rules.capture.enabled && !win_points(node, defender, rules).is_empty()) {
+            return false;
+        }
+
+        let mut replies = threats;
+        if rules.capture.enabled {
+            replies |= capture_moves(&node.grd, defender);
+        }
+        rules.del_forbidden(&node.grd, &mut replies, defender);
+        replies.iter().map(BitSet::pos).all(|r| {
+            let after = node.play(r, defender, rules);
+            !rules.is_cap_win(after.nb_cap(defender)) && !win_points_at(&after, t, attacker, rules).is_empty()
+        })
+    }
+
+    /// Cells where the attacker makes a four that wins outright, on the lines through `m`.
+    fn open_four_points(&self, node: &Node, m: Pos) -> BitSet {
+        let (rules, attacker) = (self.rules, self.attacker);
+        let mut todo = window_cells_at(&node.grd, m, attacker, rules.win_len - 2, rules.win_len);
+        rules.del_forbidden(&node.grd, &mut todo, attacker);
+        for t in todo.clone().iter() {
+            if !self.four_wins(&node.play(BitSet::pos(t), attacker, rules), BitSet::pos(t)) {
+                todo.remove(t);
+            }
+        }
+        todo
+    }
+
+    /// Answers of the defender worth looking at after the three the attacker made with `m`,
+    /// given the cells `makers` where it would follow with a winning four. Any other move
+    /// leaves one of them open.
+    fn three_replies(&self, node: &Node, m: Pos, makers: BitSet) -> BitSet {
+        let rules = self.rules;
+        let (attacker, defender) = (self.attacker, next_player(self.attacker));
+
+        // Taking a cell of the three, making a four first, or capturing stones out of it.
+        let mut todo = makers | window_cells_at(&node.grd, m, attacker, rules.win_len - 2, rules.win_len);
+        todo |= window_cells(&node.grd, defender, rules.win_len - 2, rules.win_len);
+        if rules.capture.enabled {
+            todo |= capture_moves(&node.grd, defender) | capture_threats(&node.grd, defender);
+        }
+        rules.del_forbidden(&node.grd, &mut todo, defender);
+        todo
+    }
+
+    /// Winning sequence from `node` with the attacker to move, in at most `depth` threats.
+    fn attack(&mut self, node: &Node, depth: usize) -> Option<Vec<Pos>> {
+        if self.exhausted || self.count_node() {
+            return None;
+        }
+        let rules = self.rules;
+        let (attacker, defender) = (self.attacker, next_player(self.attacker));
+
+        // A five of the defender the attacker did not break wins, whatever the attacker plays
+        // instead.
+        if rules.capture.can_break() && rules.has_five(&node.grd, defender) {
+            return None;
+        }
+        if let Some(i) = win_points(node, attacker, rules).iter().next() {
+            return Some(vec![BitSet::pos(i)]);
+        }
+        if depth == 0 {
+            return None;
+        }
+
+        // A four of the defender has to be blocked first, by a move that keeps the initiative.
+        let defender_wins = win_points(node, defender, rules);
+        let forced = !defender_wins.is_empty();
+        if defender_wins.count() > 1 {
+            return None;
+        }
+
+        let mut fours = window_cells(&node.grd, attacker, rules.win_len - 2, rules.win_len);
+        let mut threes = BitSet::new();
+        if rules.capture.enabled {
+            fours |= capture_threats(&node.grd, attacker);
+        }
+        // A three needs two more moves to win: the four, then the five.
+        if self.threes && depth >= 2 {
+            threes = fours | window_cells(&node.grd, attacker, rules.win_len - 3, rules.win_len);
+        }
+        if forced {
+            fours &= defender_wins;
+            threes &= defender_wins;
+        }
+        rules.del_forbidden(&node.grd, &mut fours, attacker);
+        rules.del_forbidden(&node.grd, &mut threes, attacker);
+
+        for &(moves, three) in [(fours, false), (threes, true)].iter() {
+            'moves: for m in moves.iter().map(BitSet::pos) {
+                if self.exhausted {
+                    return None;
+                }
+                // The attacker had no four, any four now goes through `m`. The defender had
+                // none either unless `m` blocks it, only a capture of its own may have come
+                // with `m`.
+                let next = node.play(m, attacker, rules);
+                if (forced || rules.capture.enabled) && !win_points(&next, defender, rules).is_empty() {
+                    continue;
+                }
+                let threats = win_points_at(&next, m, attacker, rules);
+                let replies = if !three {
+                    if threats.is_empty() {
+                        continue;
+                    }
+                    // Blocking a threat, or capturing stones out of it.
+                    let mut todo = threats;
+                    if rules.capture.enabled {
+                        todo |= capture_moves(&next.grd, defender);
+                    }
+                    rules.del_forbidden(&next.grd, &mut todo, defender);
+                    todo
+                } else {
+                    // Fours were all tried in the first pass.
+                    let makers = self.open_four_points(&next, m);
+                    if !threats.is_empty() || makers.is_empty() {
+                        continue;
+                    }
+                    self.three_replies(&next, m, makers)
+                };
+
+                let mut line: Option<Vec<Pos>> = None;
+                for r in replies.iter().map(BitSet::pos) {
+                    let after = next.play(r, defender, rules);
+                    if rules.is_cap_win(after.nb_cap(defender)) {
+                        continue 'moves;
+                    }
+                    if three || win_points_at(&after, m, attacker, rules).is_empty() {
+                        match self.attack(&after, depth - 1) {
+                            Some(sub) => {
+                                if line.is_none() {
+                                    line = Some([vec![r], sub].concat());
+                                }
+                            }
+                            None => continue 'moves,
+                        }
+                    }
+                }
+                // Every reply loses, to a threat left open or to the threats that follow.
+                let mut todo = vec![m];
+                todo.extend(line.unwrap_or_default());
+                return Some(todo);
+            }
+        }
+        None
+    }
+}
+
+/// Cells where `player` wins at once.
+pub fn win_moves(grd: &Board, nb_cap_white: i16, nb_cap_black: i16, player: Player, rules: &RuleSet) -> BitSet {
+    win_points(&Node { grd: *grd, nb_cap_white, nb_cap_black }, player, rules)
+}
+
+/// Cells where `player` makes a four, a line one stone short of the win.
+pub fn four_moves(grd: &Board, player: Player, rules: &RuleSet) -> BitSet {
+    let mut todo = window_cells(grd, player, rules.win_len - 2, rules.win_len);
+    rules.del_forbidden(grd, &mut todo, player);
+    todo
+}
+
+/// Forced win of `player`, to move, by a sequence of fours: its moves alternating with the
+/// defender's forced replies, and ending with the move that wins or that leaves the defender
+/// no reply. `None` when none is found within `max_depth` fours or the `limits`.
+pub fn vcf(
+    grd: &Board,
+    nb_cap_white: i16,
+    nb_cap_black: i16,
+    player: Player,
+    rules: &RuleSet,
+    max_depth: usize,
+    limits: Limits,
+) -> Option<Vec<Pos>> {
+    if rules.stones_per_turn > 1 {
+        return None;
+    }
+    let mut search = ThreatSearch::new(rules, player, false, limits);
+    search.attack(&Node { grd: *grd, nb_cap_white, nb_cap_black }, max_depth)
+}
+
+/// Forced win of `player`, to move, by a sequence of fours and open threes, in the format of
+/// `vcf`: the reply given after a three is the first one the search refuted, others may have
+/// to be answered differently. Shorter sequences are tried first.
+pub fn vct(
+    grd: &Board,
+    nb_cap_white: i16,
+    nb_cap_black: i16,
+    player: Player,
+    rules: &RuleSet,
+    max_depth: usize,
+    limits: Limits,
+) -> Option<Vec<Pos>> {
+    if rules.stones_per_turn > 1 {
+        return None;
+    }
+    let node = Node { grd: *grd, nb_cap_white, nb_cap_black };
+    let mut search = ThreatSearch::new(rules, player, true, limits);
+    for depth in 1..=max_depth {
+        if let Some(line) = search.attack(&node, depth) {
+            return Some(line);
+        }
+        if search.exhausted {
+            break;
+        }
+    }
+    None
+}
+
+/// Forced win by continuous fours of the player to move in `state`.
+pub fn find_vcf(state: &GameState, max_depth: usize, limits: Limits) -> Option<Vec<Pos>> {
+    if state.end().is_some() || state.opening_step() != OpeningStep::Done {
+        return None;
+    }
+    vcf(
+        state.board(),
+        state.nb_cap(Player::White),
+        state.nb_cap(Player::Black),
+        state.player_turn(),
+        state.rules(),
+        max_depth,
+        limits,
+    )
+}
+
+/// Forced win by continuous threats of `player` in `state`, as if it were to move.
+pub fn find_vct(state: &GameState, player: Player, max_depth: usize, limits: Limits) -> Option<Vec<Pos>> {
+    if state.end().is_some() || state.opening_step() != OpeningStep::Done {
+        return None;
+    }
+    vct(
+        state.board(),
+        state.nb_cap(Player::White),
+        state.nb_cap(Player::Black),
+        player,
+        state.rules(),
+        max_depth,
+        limits,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleKind;
+    use crate::zobrist::splitmix64;
+
+    #[test]
+    fn window_cells_matches_the_stone_by_stone_scan() {
+        let mut state = 0;
+        let mut gen_range = |low: usize, high: usize| low + (splitmix64(&mut state) % (high - low) as u64) as usize;
+        for &size in [MIN_GRID_SIZE, 9, 15, DEFAULT_GRID_SIZE, MAX_GRID_SIZE].iter() {
+            for _ in 0..40 {
+                let mut grd = Board::new(size);
+                let fill = gen_range(2, 8);
+                for y in 0..size as i16 {
+                    for x in 0..size as i16 {
+                        match gen_range(0, 2 * fill) {
+                            0 => grd.set(Pos { x, y }, CELL_BLACK),
+                            1 => grd.set(Pos { x, y }, CELL_WHITE),
+                            _ => (),
+                        }
+                    }
+                }
+
+                for &kind in RuleKind::ALL.iter() {
+                    let win_len = RuleSet::new(kind).win_len;
+                    for &player in [Player::Black, Player::White].iter() {
+                        for nb_stone in 1..win_len {
+                            let mut expected = BitSet::new();
+                            for s in grd.stones(player_to_i8(player)).iter().map(BitSet::pos) {
+                                expected |= window_cells_at(&grd, s, player, nb_stone, win_len);
+                            }
+                            assert!(
+                                window_cells(&grd, player, nb_stone, win_len) == expected,
+                                "size {}, {} stones of {} in {}",
+                                size,
+                                nb_stone,
+                                win_len,
+                                kind.name()
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::bitboard::NB_BITS;
+use crate::board::{Board, Player, CELL_BLACK};
+
+/// Capture counts above this share the same key.
+const NB_CAP_KEY: usize = 32;
+
+struct Keys {
+    stones: [[u64; NB_BITS]; 2],
+    caps: [[u64; NB_CAP_KEY]; 2],
+    white_turn: u64,
+}
+
+static KEYS: Keys = gen_keys();
+
+/// Next number of the sequence at `state`, the same on every run and platform.
+pub(crate) const fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+const fn gen_keys() -> Keys {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut keys = Keys {
+        stones: [[0; NB_BITS]; 2],
+        caps: [[0; NB_CAP_KEY]; 2],
+        white_turn: 0,
+    };
+
+    let mut c = 0;
+    while c < 2 {
+        let mut i = 0;
+        while i < NB_BITS {
+            keys.stones[c][i] = splitmix64(&mut state);
+            i += 1;
+        }
+        let mut n = 0;
+        while n < NB_CAP_KEY {
+            keys.caps[c][n] = splitmix64(&mut state);
+            n += 1;
+        }
+        c += 1;
+    }
+    keys.white_turn = splitmix64(&mut state);
+    keys
+}
+
+fn player_index(player: Player) -> usize {
+    match player {
+        Player::Black => 0,
+        Player::White => 1,
+    }
+}
+
+/// Key of a stone of colour `c` on the cell of bit index `i`.
+pub fn stone_key(i: usize, c: i8) -> u64 {
+    KEYS.stones[if c == CELL_BLACK { 0 } else { 1 }][i]
+}
+
+pub fn cap_key(player: Player, nb_cap: i16) -> u64 {
+    KEYS.caps[player_index(player)][(nb_cap.max(0) as usize).min(NB_CAP_KEY - 1)]
+}
+
+pub fn turn_key(player: Player) -> u64 {
+    match player {
+        Player::Black => 0,
+        Player::White => KEYS.white_turn,
+    }
+}
+
+/// Key of a whole position: the stones, incrementally kept by the board, the capture counts
+/// and the player to move.
+pub fn position_key(grd: &Board, nb_cap_white: i16, nb_cap_black: i16, player: Player) -> u64 {
+    grd.hash()
+        ^ cap_key(Player::White, nb_cap_white)
+        ^ cap_key(Player::Black, nb_cap_black)
+        ^ turn_key(player)
+}
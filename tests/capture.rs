@@ -0,0 +1,102 @@
+use std::time::Duration;
+use gomoku_rs::board::{CELL_EMPTY, CELL_WHITE};
+use gomoku_rs::solver::{Solver, SolverConfig, INF};
+use gomoku_rs::{CaptureRules, GameState, Player, Pos, RuleKind, RuleSet};
+
+mod common;
+use common::play_all;
+
+/// FortyTwo with its capture options replaced.
+fn rules(enabled: bool, nb_cap_win: Option<i16>, capture_break: bool) -> RuleSet {
+    RuleSet::new(RuleKind::FortyTwo).with_capture(CaptureRules { enabled, nb_cap_win, capture_break })
+}
+
+/// Black to move closes the white pair (6, 5) (7, 5) from (8, 5).
+fn pair_to_capture(rules: RuleSet) -> GameState {
+    let mut game = GameState::with_rules(19, rules);
+    play_all(&mut game, &[(5, 5), (6, 5), (9, 9), (7, 5)]);
+    game
+}
+
+const CAPTURE: Pos = Pos { x: 8, y: 5 };
+
+/// Black to move makes a five at (7, 5) that white can break from (7, 7).
+fn five_to_break(rules: RuleSet) -> GameState {
+    let mut game = GameState::with_rules(19, rules);
+    play_all(&mut game, &[(5, 5), (7, 4), (6, 5), (0, 0), (7, 6), (0, 2), (8, 5), (0, 4), (9, 5), (0, 6)]);
+    game
+}
+
+const FIVE: Pos = Pos { x: 7, y: 5 };
+
+fn solver() -> Solver {
+    Solver::new(SolverConfig { time_limit: Some(Duration::from_millis(500)), threads: 1, ..SolverConfig::default() })
+}
+
+#[test]
+fn captures_off_leave_the_pair_on_the_board() {
+    for &rules in [rules(false, Some(10), true), RuleSet::new(RuleKind::Freestyle)].iter() {
+        let mut game = pair_to_capture(rules);
+        let outcome = game.play(CAPTURE).unwrap();
+        assert!(outcome.captured.is_empty());
+        assert_eq!(game.board().get(Pos { x: 6, y: 5 }), CELL_WHITE);
+        assert_eq!(game.board().get(Pos { x: 7, y: 5 }), CELL_WHITE);
+        assert_eq!(game.nb_cap(Player::Black), 0);
+    }
+
+    let mut game = pair_to_capture(rules(true, Some(10), true));
+    game.play(CAPTURE).unwrap();
+    assert_eq!(game.board().get(Pos { x: 6, y: 5 }), CELL_EMPTY);
+}
+
+#[test]
+fn capture_wins_at_the_threshold_only() {
+    assert!(rules(true, Some(2), true).is_cap_win(2));
+    assert!(!rules(true, Some(4), true).is_cap_win(2));
+    assert!(!rules(true, None, true).is_cap_win(100));
+    assert!(!rules(false, Some(2), true).is_cap_win(2));
+
+    let mut game = pair_to_capture(rules(true, Some(2), true));
+    game.play(CAPTURE).unwrap();
+    assert_eq!(game.end(), Some(Some(Player::Black)));
+
+    for &nb_cap_win in [None, Some(4)].iter() {
+        let mut game = pair_to_capture(rules(true, nb_cap_win, true));
+        game.play(CAPTURE).unwrap();
+        assert_eq!(game.nb_cap(Player::Black), 2);
+        assert_eq!(game.end(), None, "{:?}", nb_cap_win);
+    }
+}
+
+#[test]
+fn solver_takes_the_capture_that_wins() {
+    let game = pair_to_capture(rules(true, Some(2), true));
+    let res = solver().search(&game);
+    assert_eq!(res.best_move, CAPTURE);
+    assert!(res.score > INF / 4, "score {}", res.score);
+
+    let game = pair_to_capture(rules(true, None, true));
+    let res = solver().search(&game);
+    assert!(res.score < INF / 4, "score {}", res.score);
+}
+
+#[test]
+fn five_wins_at_once_without_capture_break() {
+    let mut game = five_to_break(rules(true, Some(10), false));
+    game.play(FIVE).unwrap();
+    assert_eq!(game.pending_five(), None);
+    assert_eq!(game.end(), Some(Some(Player::Black)));
+
+    let mut game = five_to_break(rules(true, Some(10), true));
+    game.play(FIVE).unwrap();
+    assert!(game.pending_five().is_some());
+    assert_eq!(game.end(), None);
+}
+
+#[test]
+fn solver_plays_the_breakable_five_without_capture_break() {
+    let game = five_to_break(rules(true, Some(10), false));
+    let res = solver().search(&game);
+    assert_eq!(res.best_move, FIVE);
+    assert!(res.score > INF / 4, "score {}", res.score);
+}
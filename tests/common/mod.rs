@@ -0,0 +1,48 @@
+//! Positions and helpers shared by the integration tests, each of which uses only some.
+#![allow(dead_code)]
+
+use gomoku_rs::{GameState, Pos, RuleSet};
+
+pub fn play_all(game: &mut GameState, moves: &[(i16, i16)]) {
+    for &(x, y) in moves.iter() {
+        game.play(Pos { x, y }).unwrap();
+    }
+}
+
+/// Black to move with a row three and a column three, both blocked on one side by white.
+pub fn two_threes(rules: RuleSet) -> GameState {
+    let mut game = GameState::with_rules(19, rules);
+    play_all(&mut game, &[
+        (5, 5), (4, 5), (6, 5), (0, 0), (7, 5), (0, 2),
+        (10, 8), (9, 8), (11, 8), (10, 11), (12, 8), (0, 4),
+        (10, 9), (0, 6), (10, 10), (0, 8),
+    ]);
+    game
+}
+
+/// Xorshift generator, deterministic across runs and platforms.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn gen_range(&mut self, low: usize, high: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        low + (self.0 % ((high - low) as u64)) as usize
+    }
+}
+
+/// Legal move of the player to move, next to the stones nine times out of ten.
+pub fn random_move(game: &GameState, rng: &mut Rng) -> Pos {
+    let near = game.board().occupied().dilate();
+    let lpos: Vec<Pos> = game.legal_moves();
+    let lnear: Vec<Pos> = lpos.iter().cloned()
+        .filter(|p| near.contains(*p))
+        .collect();
+
+    if lnear.is_empty() || rng.gen_range(0, 10) == 0 {
+        lpos[rng.gen_range(0, lpos.len())]
+    } else {
+        lnear[rng.gen_range(0, lnear.len())]
+    }
+}
@@ -0,0 +1,175 @@
+use gomoku_rs::board::*;
+use gomoku_rs::{CaptureRules, Player, Pos, RuleKind, RuleSet};
+
+mod common;
+use common::Rng;
+
+const SIZE: usize = 19;
+const CENTER: i16 = 9;
+/// Cells on each side of the move that can take part in a free three through it.
+const REACH: usize = 4;
+const LEN_LINE: usize = 2 * REACH + 1;
+
+/// Free three by the book, on the cells around the move with the move at `REACH`: `.` empty,
+/// `x` own stone, `o` opponent stone, `#` off the board. The move makes a free three when one
+/// more own stone yields, with the move, exactly four stones in a row between two empty cells.
+fn ref_free_three(line: &[u8; LEN_LINE]) -> bool {
+    let mut line = *line;
+    line[REACH] = b'x';
+    (0..LEN_LINE).filter(|&q| line[q] == b'.').any(|q| {
+        let mut four = line;
+        four[q] = b'x';
+        (1..=LEN_LINE - 5).any(|a| {
+            let run = a..a + 4;
+            run.clone().all(|i| four[i] == b'x')
+                && four[a - 1] == b'.'
+                && four[a + 4] == b'.'
+                && run.contains(&REACH)
+                && run.contains(&q)
+        })
+    })
+}
+
+/// Cell `k` steps from `p` along `d`.
+fn at(p: Pos, (dx, dy): (i16, i16), k: i16) -> Pos {
+    Pos { x: p.x + dx * k, y: p.y + dy * k }
+}
+
+/// Lays `line` on `grd` along `d` through `p`, with `x` as `player` stones, and marks the cells
+/// off the board with `#`.
+fn lay_line(grd: &mut Board, p: Pos, d: (i16, i16), line: &mut [u8; LEN_LINE], player: Player) {
+    for (i, cell) in line.iter_mut().enumerate() {
+        let q = at(p, d, i as i16 - REACH as i16);
+        if !grd.contains(q) {
+            *cell = b'#';
+            continue;
+        }
+        match *cell {
+            b'x' => grd.set(q, player_to_i8(player)),
+            b'o' => grd.set(q, player_to_i8(next_player(player))),
+            _ => (),
+        }
+    }
+}
+
+/// Every line of empty cells and stones of both colours, for the move on the centre and at
+/// each distance from the edges, along the four directions.
+#[test]
+fn free_three_matches_reference_on_every_line() {
+    let mut nb_free = 0;
+    for i in 0..(NB_DIR / 2) {
+        let d = ALL_DIR[i * 2];
+        for &s in [-9, -8, -7, -6, -5, 0, 5, 6, 7, 8, 9].iter() {
+            let p = at(Pos { x: CENTER, y: CENTER }, d, s);
+            let empty = Board::new(SIZE);
+            let inside: Vec<usize> = (0..LEN_LINE)
+                .filter(|&j| j != REACH && empty.contains(at(p, d, j as i16 - REACH as i16)))
+                .collect();
+            for code in 0..3usize.pow(inside.len() as u32) {
+                let mut line = [b'.'; LEN_LINE];
+                let mut rest = code;
+                for &j in inside.iter() {
+                    line[j] = [b'.', b'x', b'o'][rest % 3];
+                    rest /= 3;
+                }
+                let mut grd = Board::new(SIZE);
+                lay_line(&mut grd, p, d, &mut line, Player::Black);
+
+                let expected = ref_free_three(&line);
+                nb_free += expected as usize;
+                assert_eq!(
+                    is_free_three_dir(&grd, p, d, CELL_BLACK),
+                    expected,
+                    "line {} along {:?} at {:?}",
+                    String::from_utf8_lossy(&line),
+                    d,
+                    p,
+                );
+                assert_eq!(nb_free_three(&grd, p, CELL_BLACK), expected as usize);
+            }
+        }
+    }
+    assert!(nb_free > 0);
+}
+
+#[test]
+fn double_three_matches_reference_on_crossing_lines() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let mut nb_double = 0;
+
+    for _ in 0..20000 {
+        let p = Pos { x: rng.gen_range(0, SIZE) as i16, y: rng.gen_range(0, SIZE) as i16 };
+        let mut grd = Board::new(SIZE);
+        let mut nb_free = 0;
+        for i in 0..(NB_DIR / 2) {
+            let mut line = [b'.'; LEN_LINE];
+            for (j, cell) in line.iter_mut().enumerate() {
+                if j != REACH {
+                    // Mostly own stones and empty cells, for threes to show up.
+                    *cell = [b'.', b'.', b'x', b'x', b'o'][rng.gen_range(0, 5)];
+                }
+            }
+            lay_line(&mut grd, p, ALL_DIR[i * 2], &mut line, Player::White);
+            nb_free += ref_free_three(&line) as usize;
+        }
+
+        let captures = countcap(&grd, p, Player::White) > 0;
+        nb_double += (nb_free >= 2) as usize;
+        assert_eq!(nb_free_three(&grd, p, CELL_WHITE), nb_free);
+        assert_eq!(is_double_three(&grd, p, Player::White, false), nb_free >= 2);
+        assert_eq!(is_double_three(&grd, p, Player::White, true), nb_free >= 2 && !captures);
+    }
+    assert!(nb_double > 0);
+}
+
+/// Black on the centre makes a free three on its row and one on its column.
+fn double_three_board() -> (Board, Pos) {
+    let p = Pos { x: CENTER, y: CENTER };
+    let mut grd = Board::new(SIZE);
+    for &(x, y) in [(10, 9), (11, 9), (9, 10), (9, 11)].iter() {
+        grd.set(Pos { x, y }, CELL_BLACK);
+    }
+    (grd, p)
+}
+
+#[test]
+fn double_three_is_forbidden() {
+    let (grd, p) = double_three_board();
+    let rules = RuleSet::new(RuleKind::FortyTwo);
+    let mut vld = grd.empty();
+    rules.del_forbidden(&grd, &mut vld, Player::Black);
+    assert!(!vld.contains(p));
+    assert!(vld.contains(Pos { x: 12, y: 9 }));
+}
+
+#[test]
+fn double_three_allowed_when_capturing() {
+    let (mut grd, p) = double_three_board();
+    grd.set(Pos { x: 8, y: 8 }, CELL_WHITE);
+    grd.set(Pos { x: 7, y: 7 }, CELL_WHITE);
+    grd.set(Pos { x: 6, y: 6 }, CELL_BLACK);
+
+    let rules = RuleSet::new(RuleKind::FortyTwo);
+    let mut vld = grd.empty();
+    rules.del_forbidden(&grd, &mut vld, Player::Black);
+    assert!(vld.contains(p));
+
+    let rules = rules.with_capture(CaptureRules::NONE);
+    let mut vld = grd.empty();
+    rules.del_forbidden(&grd, &mut vld, Player::Black);
+    assert!(!vld.contains(p));
+}
+
+#[test]
+fn blocked_three_is_not_free() {
+    let p = Pos { x: CENTER, y: CENTER };
+    let mut grd = Board::new(SIZE);
+    grd.set(Pos { x: 10, y: 9 }, CELL_BLACK);
+    grd.set(Pos { x: 11, y: 9 }, CELL_BLACK);
+    assert!(is_free_three_dir(&grd, p, (1, 0), CELL_BLACK));
+
+    grd.set(Pos { x: 13, y: 9 }, CELL_WHITE);
+    assert!(is_free_three_dir(&grd, p, (1, 0), CELL_BLACK));
+    grd.set(Pos { x: 7, y: 9 }, CELL_WHITE);
+    assert!(!is_free_three_dir(&grd, p, (1, 0), CELL_BLACK));
+}
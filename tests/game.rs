@@ -0,0 +1,174 @@
+use gomoku_rs::board::{CELL_EMPTY, CELL_WHITE};
+use gomoku_rs::{GameState, Opening, OpeningStep, Player, Pos, RuleKind, RuleSet, Seat, SwapChoice};
+
+mod common;
+use common::play_all;
+
+/// Black closes a pair of white stones on a row and captures it.
+fn captured_pair() -> GameState {
+    let mut game = GameState::with_rules(19, RuleSet::new(RuleKind::FortyTwo));
+    play_all(&mut game, &[(5, 5), (6, 5), (9, 9), (7, 5), (8, 5)]);
+    game
+}
+
+#[test]
+fn capture_removes_the_pair_and_counts_it() {
+    let game = captured_pair();
+    let last = game.history().last().unwrap();
+    assert_eq!(last.captured.len(), 2);
+    assert!(last.captured.contains(&Pos { x: 6, y: 5 }));
+    assert!(last.captured.contains(&Pos { x: 7, y: 5 }));
+    assert_eq!(game.board().get(Pos { x: 6, y: 5 }), CELL_EMPTY);
+    assert_eq!(game.board().get(Pos { x: 7, y: 5 }), CELL_EMPTY);
+    assert_eq!(game.nb_cap(Player::Black), 2);
+    assert_eq!(game.nb_cap(Player::White), 0);
+}
+
+#[test]
+fn undo_puts_captured_stones_back() {
+    let mut game = captured_pair();
+    let mut before = GameState::with_rules(19, RuleSet::new(RuleKind::FortyTwo));
+    play_all(&mut before, &[(5, 5), (6, 5), (9, 9), (7, 5)]);
+
+    let outcome = game.undo().unwrap();
+    assert_eq!(outcome.pos, Pos { x: 8, y: 5 });
+    assert!(*game.board() == *before.board());
+    assert_eq!(game.board().get(Pos { x: 6, y: 5 }), CELL_WHITE);
+    assert_eq!(game.board().get(Pos { x: 7, y: 5 }), CELL_WHITE);
+    assert_eq!(game.board().get(Pos { x: 8, y: 5 }), CELL_EMPTY);
+    assert_eq!(game.nb_cap(Player::Black), 0);
+    assert_eq!(game.player_turn(), Player::Black);
+    assert_eq!(game.nb_move(), 4);
+}
+
+#[test]
+fn redo_replays_the_capture() {
+    let mut game = captured_pair();
+    let after = *game.board();
+    game.undo();
+    assert!(game.can_redo());
+
+    let outcome = game.redo().unwrap();
+    assert_eq!(outcome.captured.len(), 2);
+    assert!(*game.board() == after);
+    assert_eq!(game.nb_cap(Player::Black), 2);
+    assert_eq!(game.player_turn(), Player::White);
+    assert!(!game.can_redo());
+}
+
+#[test]
+fn undo_redo_round_trip_the_whole_game() {
+    let mut game = captured_pair();
+    let end = game.clone();
+    while game.undo().is_some() {}
+    assert_eq!(game.nb_move(), 0);
+    assert!(game.board().occupied().is_empty());
+    assert!(!game.can_undo());
+
+    while game.redo().is_some() {}
+    assert!(*game.board() == *end.board());
+    assert_eq!(game.history(), end.history());
+    assert_eq!(game.nb_cap(Player::Black), end.nb_cap(Player::Black));
+}
+
+#[test]
+fn playing_after_undo_drops_the_redo_line() {
+    let mut game = captured_pair();
+    game.undo();
+    game.play(Pos { x: 12, y: 12 }).unwrap();
+    assert!(!game.can_redo());
+    assert_eq!(game.redo(), None);
+}
+
+#[test]
+fn full_board_without_five_is_a_draw() {
+    // Pairs shifted by two cells from a row to the next: no line holds five of a colour.
+    let (black, white): (Vec<Pos>, Vec<Pos>) = (0..5)
+        .flat_map(|y| (0..5).map(move |x| Pos { x, y }))
+        .partition(|p| (p.x + 2 * p.y) % 4 < 2);
+    assert_eq!((black.len(), white.len()), (13, 12));
+
+    let mut game = GameState::with_rules(5, RuleSet::new(RuleKind::Freestyle));
+    for (i, &p) in black.iter().enumerate() {
+        assert_eq!(game.end(), None);
+        game.play(p).unwrap();
+        if let Some(&q) = white.get(i) {
+            assert_eq!(game.end(), None);
+            game.play(q).unwrap();
+        }
+    }
+    assert!(game.board().empty().is_empty());
+    assert_eq!(game.end(), Some(None));
+    assert_eq!(game.winner(), None);
+    assert!(game.legal_moves().is_empty());
+}
+
+#[test]
+fn undo_redo_go_through_a_swap2_opening_and_back() {
+    let mut game = GameState::with_rules(15, RuleSet::new(RuleKind::Freestyle).with_opening(Opening::Swap2));
+    play_all(&mut game, &[(7, 7), (8, 8), (9, 7)]);
+    game.choose(SwapChoice::PlaceTwo).unwrap();
+    play_all(&mut game, &[(6, 6), (10, 10)]);
+    game.choose(SwapChoice::White).unwrap();
+    play_all(&mut game, &[(5, 5), (11, 11)]);
+    assert_eq!(game.seat_of(Player::Black), Seat::Second);
+    let played = game.clone();
+
+    let mut steps = Vec::new();
+    while game.can_undo() {
+        game.undo();
+        steps.push(game.opening_step());
+    }
+    // A stone goes back with the answers given after it.
+    assert_eq!(steps[1], OpeningStep::Done);
+    assert_eq!(steps[2], OpeningStep::Place(Seat::Second));
+    assert_eq!(steps[3], OpeningStep::Place(Seat::Second));
+    assert_eq!(steps[4], OpeningStep::Place(Seat::First));
+
+    for step in steps.iter().rev().skip(1) {
+        assert!(game.redo().is_some());
+        assert_eq!(game.opening_step(), *step);
+    }
+    assert!(game.redo().is_some());
+    assert!(!game.can_redo());
+    assert!(game.redo().is_none());
+    assert!(game.board() == played.board());
+    assert_eq!(game.opening_step(), OpeningStep::Done);
+    assert_eq!(game.seat_of(Player::Black), Seat::Second);
+    assert_eq!(game.seat_to_move(), played.seat_to_move());
+    assert_eq!(game.history(), played.history());
+}
+
+#[test]
+fn connect6_turns_lay_two_stones_after_the_first_one() {
+    let mut game = GameState::with_rules(19, RuleSet::new(RuleKind::Connect6));
+    let mut players = Vec::new();
+    for i in 0..9 {
+        players.push(game.player_turn());
+        game.play(Pos { x: i, y: 0 }).unwrap();
+    }
+    use Player::{Black as B, White as W};
+    assert_eq!(players, vec![B, W, W, B, B, W, W, B, B]);
+    assert_eq!(game.player_turn(), W);
+}
+
+#[test]
+fn rounds_count_a_turn_of_each_player() {
+    let rounds = |kind: RuleKind| (0..9).map(|n| RuleSet::new(kind).round_of_move(n)).collect::<Vec<_>>();
+    assert_eq!(rounds(RuleKind::Freestyle), vec![1, 1, 2, 2, 3, 3, 4, 4, 5]);
+    // Black's single first stone, then two stones a turn.
+    assert_eq!(rounds(RuleKind::Connect6), vec![1, 1, 1, 2, 2, 2, 2, 3, 3]);
+}
+
+#[test]
+fn connect6_keeps_captures_off() {
+    let capture = RuleSet::new(RuleKind::FortyTwo).capture;
+    let rules = RuleSet::new(RuleKind::Connect6).with_capture(capture);
+    assert!(!rules.capture.enabled);
+
+    // Black flanks the white pair at (6,5) and (7,5).
+    let mut game = GameState::with_rules(19, rules);
+    play_all(&mut game, &[(5, 5), (6, 5), (7, 5), (8, 5), (0, 0)]);
+    assert_eq!(game.board().get(Pos { x: 6, y: 5 }), CELL_WHITE);
+    assert_eq!(game.nb_cap(Player::Black), 0);
+}
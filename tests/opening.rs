@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+use gomoku_rs::solver::{Decision, Solver, SolverConfig};
+use gomoku_rs::{GameState, IllegalMove, Opening, OpeningStep, Pos, RuleKind, RuleSet, Seat, SwapChoice};
+
+fn pro_game(opening: Opening) -> GameState {
+    GameState::with_rules(15, RuleSet::new(RuleKind::Standard).with_opening(opening))
+}
+
+/// Centre for black, then a white stone next to it: black lays its second stone.
+fn second_black_stone(opening: Opening) -> GameState {
+    let mut game = pro_game(opening);
+    game.play(Pos { x: 7, y: 7 }).unwrap();
+    game.play(Pos { x: 8, y: 7 }).unwrap();
+    game
+}
+
+#[test]
+fn pro_first_stone_goes_in_the_centre() {
+    for &opening in [Opening::Pro, Opening::LongPro].iter() {
+        let game = pro_game(opening);
+        assert_eq!(game.check_move(Pos { x: 6, y: 7 }), Err(IllegalMove::OpeningRestriction));
+        assert_eq!(game.legal_moves(), vec![Pos { x: 7, y: 7 }]);
+    }
+}
+
+#[test]
+fn pro_keeps_the_second_black_stone_three_cells_away() {
+    let game = second_black_stone(Opening::Pro);
+    for &(x, y) in [(9, 9), (5, 7), (7, 9), (5, 5), (9, 6)].iter() {
+        assert_eq!(game.check_move(Pos { x, y }), Err(IllegalMove::OpeningRestriction), "{:?}", (x, y));
+    }
+    for &(x, y) in [(10, 7), (4, 4), (7, 10), (10, 5), (0, 0)].iter() {
+        assert!(game.is_legal(Pos { x, y }), "{:?}", (x, y));
+    }
+    // The 5x5 square around the centre is out, the white stone being inside already.
+    assert_eq!(game.legal_moves().len(), 15 * 15 - 25);
+}
+
+#[test]
+fn long_pro_keeps_the_second_black_stone_four_cells_away() {
+    let game = second_black_stone(Opening::LongPro);
+    for &(x, y) in [(10, 7), (4, 4), (7, 10), (10, 5)].iter() {
+        assert_eq!(game.check_move(Pos { x, y }), Err(IllegalMove::OpeningRestriction), "{:?}", (x, y));
+    }
+    for &(x, y) in [(11, 7), (3, 3), (7, 11), (11, 4)].iter() {
+        assert!(game.is_legal(Pos { x, y }), "{:?}", (x, y));
+    }
+    assert_eq!(game.legal_moves().len(), 15 * 15 - 49);
+}
+
+#[test]
+fn pro_leaves_white_and_later_black_stones_free() {
+    let mut game = pro_game(Opening::Pro);
+    game.play(Pos { x: 7, y: 7 }).unwrap();
+    assert!(game.is_legal(Pos { x: 7, y: 8 }));
+    assert_eq!(game.legal_moves().len(), 15 * 15 - 1);
+
+    game.play(Pos { x: 7, y: 8 }).unwrap();
+    game.play(Pos { x: 11, y: 11 }).unwrap();
+    assert!(game.is_legal(Pos { x: 8, y: 8 }));
+    game.play(Pos { x: 8, y: 8 }).unwrap();
+    assert!(game.is_legal(Pos { x: 6, y: 6 }));
+}
+
+#[test]
+fn swap2_stones_are_laid_within_the_time_limit() {
+    let mut game = GameState::with_rules(19, RuleSet::new(RuleKind::FortyTwo).with_opening(Opening::Swap2));
+    for &(x, y) in [(9, 9), (10, 10), (11, 9)].iter() {
+        game.play(Pos { x, y }).unwrap();
+    }
+    game.choose(SwapChoice::PlaceTwo).unwrap();
+    game.play(Pos { x: 8, y: 8 }).unwrap();
+    assert_eq!(game.opening_step(), OpeningStep::Place(Seat::Second));
+
+    let limit = Duration::from_millis(50);
+    let solver = Solver::new(SolverConfig { time_limit: Some(limit), threads: 1, ..SolverConfig::default() });
+    let start = Instant::now();
+    let decision = solver.decide(&game);
+    assert!(start.elapsed() < limit + Duration::from_millis(30), "{:?}", start.elapsed());
+    match decision {
+        Decision::Play(res) => assert!(game.is_legal(res.best_move)),
+        Decision::Choose(..) => panic!("a stone to lay"),
+    }
+}
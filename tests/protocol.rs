@@ -0,0 +1,139 @@
+use std::time::{Duration, Instant};
+use gomoku_rs::protocol::Brain;
+use gomoku_rs::solver::DEFAULT_TIME_LIMIT_MS;
+use gomoku_rs::{Player, Pos, RuleKind};
+
+/// Brain on a 15x15 board, quick to answer.
+fn brain() -> Brain {
+    let mut brain = Brain::new();
+    assert_eq!(brain.command("START 15").as_deref(), Some("OK"));
+    assert_eq!(brain.command("INFO timeout_turn 50"), None);
+    brain
+}
+
+/// Move on the last line of an answer.
+fn answer_move(answer: &str) -> Pos {
+    let mut it = answer.lines().last().unwrap().split(',').map(|v| v.parse::<i16>().unwrap());
+    Pos { x: it.next().unwrap(), y: it.next().unwrap() }
+}
+
+fn send_board(brain: &mut Brain, stones: &[(i16, i16, u8)]) -> String {
+    assert_eq!(brain.command("BOARD"), None);
+    for &(x, y, field) in stones.iter() {
+        assert_eq!(brain.command(&format!("{},{},{}", x, y, field)), None);
+    }
+    brain.command("DONE").unwrap()
+}
+
+#[test]
+fn board_is_rebuilt_from_the_side_with_more_stones() {
+    let mut brain = brain();
+    let answer = send_board(&mut brain, &[(7, 7, 2), (8, 8, 1), (6, 6, 2)]);
+
+    let history = brain.game().history();
+    let played: Vec<_> = history.iter().map(|o| (o.player, o.pos)).collect();
+    assert_eq!(played[..3], [
+        (Player::Black, Pos { x: 7, y: 7 }),
+        (Player::White, Pos { x: 8, y: 8 }),
+        (Player::Black, Pos { x: 6, y: 6 }),
+    ]);
+    assert_eq!(played.len(), 4);
+    assert_eq!(played[3], (Player::White, answer_move(&answer)));
+}
+
+#[test]
+fn board_with_as_many_stones_starts_with_the_brain() {
+    let mut brain = brain();
+    let answer = send_board(&mut brain, &[(7, 7, 2), (8, 8, 1)]);
+
+    let history = brain.game().history();
+    assert_eq!(history[0].pos, Pos { x: 8, y: 8 });
+    assert_eq!(history[1].pos, Pos { x: 7, y: 7 });
+    assert_eq!(history[2].pos, answer_move(&answer));
+    assert_eq!(history[2].player, Player::Black);
+}
+
+#[test]
+fn board_out_of_turn_is_refused() {
+    let mut brain = brain();
+    assert_eq!(send_board(&mut brain, &[(7, 7, 1), (8, 8, 1)]), "ERROR stones out of turn");
+    assert_eq!(send_board(&mut brain, &[(7, 7, 2), (8, 8, 2), (9, 9, 2)]), "ERROR stones out of turn");
+}
+
+#[test]
+fn takeback_only_undoes_the_last_move() {
+    let mut brain = brain();
+    let answer = brain.command("TURN 7,7").unwrap();
+    let own = answer_move(&answer);
+    assert_eq!(brain.game().nb_move(), 2);
+
+    assert_eq!(brain.command("TAKEBACK 7,7").as_deref(), Some("ERROR not the last move"));
+    assert_eq!(brain.command(&format!("TAKEBACK {},{}", own.x, own.y)).as_deref(), Some("OK"));
+    assert_eq!(brain.game().last_move(), Some(Pos { x: 7, y: 7 }));
+    assert_eq!(brain.command("TAKEBACK 7,7").as_deref(), Some("OK"));
+    assert_eq!(brain.game().nb_move(), 0);
+    assert!(brain.command("TAKEBACK 7,7").unwrap().starts_with("ERROR"));
+}
+
+#[test]
+fn info_rule_bits_pick_the_rules() {
+    let mut brain = brain();
+    for &(bits, kind) in [
+        (0, RuleKind::Freestyle),
+        (1, RuleKind::Standard),
+        (2, RuleKind::Freestyle),
+        (4, RuleKind::Renju),
+        (5, RuleKind::Renju),
+        (8, RuleKind::Caro),
+        (9, RuleKind::Caro),
+    ].iter() {
+        assert_eq!(brain.command(&format!("INFO rule {}", bits)), None);
+        assert_eq!(brain.game().rules().kind, kind, "rule {}", bits);
+    }
+}
+
+#[test]
+fn info_rule_waits_for_the_next_game() {
+    let mut brain = brain();
+    brain.command("TURN 7,7").unwrap();
+    brain.command("INFO rule 4");
+    assert_eq!(brain.game().rules().kind, RuleKind::Freestyle);
+    assert_eq!(brain.command("RESTART").as_deref(), Some("OK"));
+    assert_eq!(brain.game().rules().kind, RuleKind::Renju);
+}
+
+#[test]
+fn time_limit_takes_the_turn_and_match_limits() {
+    let ms = Duration::from_millis;
+    let mut brain = Brain::new();
+    assert_eq!(brain.time_limit(), ms(DEFAULT_TIME_LIMIT_MS - 30));
+
+    brain.command("INFO timeout_turn 1000");
+    assert_eq!(brain.time_limit(), ms(970));
+    // A twentieth of the match time, when shorter, until the time left is known.
+    brain.command("INFO timeout_match 10000");
+    assert_eq!(brain.time_limit(), ms(470));
+    brain.command("INFO time_left 100000");
+    assert_eq!(brain.time_limit(), ms(970));
+    brain.command("INFO time_left 100");
+    assert_eq!(brain.time_limit(), ms(1));
+
+    // No turn limit asks for the fastest answers, with or without a match limit.
+    brain.command("INFO timeout_turn 0");
+    brain.command("INFO time_left 2000");
+    assert_eq!(brain.time_limit(), ms(20));
+    brain.command("INFO timeout_match 0");
+    assert_eq!(brain.time_limit(), ms(20));
+}
+
+#[test]
+fn fastest_answers_come_at_once() {
+    let mut brain = brain();
+    brain.command("INFO timeout_turn 0");
+    brain.command("INFO timeout_match 0");
+    let start = Instant::now();
+    let answer = brain.command("TURN 7,7").unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2), "{:?}", start.elapsed());
+    assert_eq!(brain.game().nb_move(), 2);
+    assert_eq!(brain.game().last_move(), Some(answer_move(&answer)));
+}
@@ -0,0 +1,137 @@
+use gomoku_rs::board::*;
+use gomoku_rs::renju::is_forbidden;
+use gomoku_rs::{GameState, IllegalMove, Player, Pos, RuleKind, RuleSet};
+
+const MOVE: Pos = Pos { x: 7, y: 7 };
+
+fn board(black: &[(i16, i16)], white: &[(i16, i16)]) -> Board {
+    let mut grd = Board::new(15);
+    for &(x, y) in black.iter() {
+        grd.set(Pos { x, y }, CELL_BLACK);
+    }
+    for &(x, y) in white.iter() {
+        grd.set(Pos { x, y }, CELL_WHITE);
+    }
+    grd
+}
+
+#[test]
+fn double_three_is_forbidden() {
+    let grd = board(&[(5, 7), (6, 7), (7, 5), (7, 6)], &[]);
+    assert!(is_forbidden(&grd, MOVE));
+}
+
+#[test]
+fn three_against_a_white_stone_is_no_three() {
+    // The row can only become a four with a white stone at one end.
+    let grd = board(&[(5, 7), (6, 7), (7, 5), (7, 6)], &[(4, 7)]);
+    assert!(!is_forbidden(&grd, MOVE));
+}
+
+#[test]
+fn double_four_is_forbidden() {
+    let grd = board(&[(4, 7), (5, 7), (6, 7), (7, 4), (7, 5), (7, 6)], &[(3, 7), (7, 3)]);
+    assert!(is_forbidden(&grd, MOVE));
+}
+
+#[test]
+fn double_four_on_a_single_line_is_forbidden() {
+    // x x x . M . x x x: both gaps make a five.
+    let grd = board(&[(3, 7), (4, 7), (5, 7), (9, 7), (10, 7), (11, 7)], &[]);
+    assert!(is_forbidden(&grd, MOVE));
+    // x . x M x . x
+    let grd = board(&[(4, 7), (6, 7), (8, 7), (10, 7)], &[]);
+    assert!(is_forbidden(&grd, MOVE));
+}
+
+#[test]
+fn overline_is_forbidden() {
+    let grd = board(&[(3, 7), (4, 7), (5, 7), (6, 7), (8, 7)], &[]);
+    assert!(is_forbidden(&grd, MOVE));
+}
+
+#[test]
+fn four_three_is_allowed() {
+    let grd = board(&[(4, 7), (5, 7), (6, 7), (7, 5), (7, 6)], &[(3, 7)]);
+    assert!(!is_forbidden(&grd, MOVE));
+}
+
+#[test]
+fn five_beats_overline() {
+    // Five on the row, seven stones on the column.
+    let black = [(3, 7), (4, 7), (5, 7), (6, 7), (7, 4), (7, 5), (7, 6), (7, 8), (7, 9), (7, 10)];
+    let grd = board(&black, &[]);
+    assert!(is_forbidden(&board(&black[4..], &[]), MOVE));
+    assert!(!is_forbidden(&grd, MOVE));
+}
+
+#[test]
+fn five_wins_for_black_on_a_foul_point() {
+    let mut game = GameState::with_rules(15, RuleSet::new(RuleKind::Renju));
+    let black = [(3, 7), (4, 7), (5, 7), (6, 7), (7, 4), (7, 5), (7, 6), (7, 8), (7, 9), (7, 10)];
+    for (i, &(x, y)) in black.iter().enumerate() {
+        game.play(Pos { x, y }).unwrap();
+        game.play(Pos { x: 2 * (i as i16 % 7), y: 14 - 2 * (i as i16 / 7) }).unwrap();
+    }
+    game.play(MOVE).unwrap();
+    assert_eq!(game.winner(), Some(Player::Black));
+}
+
+#[test]
+fn three_whose_four_point_is_a_foul_is_no_three() {
+    // The row three only becomes a straight four at (8, 7), and black may not play there: it
+    // would make six on the column.
+    let row_and_column = [(5, 7), (6, 7), (7, 5), (7, 6)];
+    let overline = [(8, 6), (8, 8), (8, 9), (8, 10), (8, 11)];
+    let white = [(3, 7)];
+
+    let grd = board(&row_and_column, &white);
+    assert!(is_forbidden(&grd, MOVE));
+
+    let black: Vec<_> = row_and_column.iter().chain(overline.iter()).cloned().collect();
+    let grd = board(&black, &white);
+    let mut with_move = grd;
+    with_move.set(MOVE, CELL_BLACK);
+    assert!(is_forbidden(&with_move, Pos { x: 8, y: 7 }));
+    assert!(!is_forbidden(&grd, MOVE));
+}
+
+#[test]
+fn white_is_never_forbidden() {
+    let rules = RuleSet::new(RuleKind::Renju);
+    let shapes: [&[(i16, i16)]; 3] = [
+        &[(5, 7), (6, 7), (7, 5), (7, 6)],
+        &[(3, 7), (4, 7), (5, 7), (9, 7), (10, 7), (11, 7)],
+        &[(3, 7), (4, 7), (5, 7), (6, 7), (8, 7)],
+    ];
+    for shape in shapes.iter() {
+        let grd = board(&[], shape);
+        let mut vld = empty_pos(&grd);
+        rules.del_forbidden(&grd, &mut vld, Player::White);
+        assert!(vld.contains(MOVE));
+        assert!(vld == empty_pos(&grd));
+
+        let grd = board(shape, &[]);
+        let mut vld = empty_pos(&grd);
+        rules.del_forbidden(&grd, &mut vld, Player::Black);
+        assert!(!vld.contains(MOVE));
+    }
+}
+
+#[test]
+fn foul_is_refused_to_black_only() {
+    let mut game = GameState::with_rules(15, RuleSet::new(RuleKind::Renju));
+    for &(b, w) in [((5, 7), (5, 1)), ((6, 7), (6, 1)), ((7, 5), (8, 1)), ((7, 6), (9, 1))].iter() {
+        game.play(Pos { x: b.0, y: b.1 }).unwrap();
+        game.play(Pos { x: w.0, y: w.1 }).unwrap();
+    }
+    assert_eq!(game.check_move(MOVE), Err(IllegalMove::Forbidden));
+
+    let mut game = GameState::with_rules(15, RuleSet::new(RuleKind::Renju));
+    for &(b, w) in [((1, 13), (5, 7)), ((3, 13), (6, 7)), ((5, 13), (7, 5)), ((9, 13), (7, 6)), ((11, 13), (0, 0))].iter() {
+        game.play(Pos { x: b.0, y: b.1 }).unwrap();
+        game.play(Pos { x: w.0, y: w.1 }).unwrap();
+    }
+    game.play(Pos { x: 13, y: 13 }).unwrap();
+    assert!(game.is_legal(MOVE));
+}
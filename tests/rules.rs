@@ -0,0 +1,93 @@
+use std::time::Duration;
+use gomoku_rs::solver::{Solver, SolverConfig};
+use gomoku_rs::{GameState, IllegalMove, Opening, Player, Pos, RuleKind, RuleSet};
+
+mod common;
+use common::play_all;
+
+/// Black row of five with a gap at (6, 9), which makes six, the other side having played the
+/// column at x = 0 with gaps.
+fn overline_gap(rules: RuleSet, player: Player) -> GameState {
+    let mut game = GameState::with_rules(19, rules);
+    let row = [(3, 9), (4, 9), (5, 9), (7, 9), (8, 9)];
+    let other = [(0, 0), (0, 2), (0, 4), (0, 6), (0, 8), (0, 10)];
+    for i in 0..row.len() {
+        if player == Player::White {
+            play_all(&mut game, &[other[i]]);
+        }
+        play_all(&mut game, &[row[i]]);
+        if player == Player::Black {
+            play_all(&mut game, &[other[i]]);
+        }
+    }
+    if player == Player::White {
+        play_all(&mut game, &[other[5]]);
+    }
+    assert_eq!(game.player_turn(), player);
+    game
+}
+
+/// Black, to move, makes two open threes at (7, 7), white having played in the corners.
+fn double_three(rules: RuleSet) -> GameState {
+    let mut game = GameState::with_rules(19, rules);
+    play_all(&mut game, &[(5, 7), (0, 0), (6, 7), (0, 18), (7, 5), (18, 0), (7, 6), (18, 18)]);
+    game
+}
+
+const OVERLINE: Pos = Pos { x: 6, y: 9 };
+const DOUBLE_THREE: Pos = Pos { x: 7, y: 7 };
+
+#[test]
+fn overline_wins_in_freestyle_only() {
+    let mut game = overline_gap(RuleSet::new(RuleKind::Freestyle), Player::Black);
+    game.play(OVERLINE).unwrap();
+    assert_eq!(game.end(), Some(Some(Player::Black)));
+
+    let mut game = overline_gap(RuleSet::new(RuleKind::Standard), Player::Black);
+    game.play(OVERLINE).unwrap();
+    assert_eq!(game.end(), None);
+}
+
+#[test]
+fn double_three_is_allowed_in_freestyle() {
+    for &kind in [RuleKind::Freestyle, RuleKind::Standard].iter() {
+        assert!(double_three(RuleSet::new(kind)).is_legal(DOUBLE_THREE), "{}", kind.name());
+    }
+    assert_eq!(double_three(RuleSet::new(RuleKind::FortyTwo)).check_move(DOUBLE_THREE), Err(IllegalMove::DoubleThree));
+    assert_eq!(double_three(RuleSet::new(RuleKind::Renju)).check_move(DOUBLE_THREE), Err(IllegalMove::Forbidden));
+}
+
+#[test]
+fn renju_restricts_black_only() {
+    let rules = RuleSet::new(RuleKind::Renju);
+    assert_eq!(overline_gap(rules, Player::Black).check_move(OVERLINE), Err(IllegalMove::Forbidden));
+    let mut game = overline_gap(rules, Player::White);
+    game.play(OVERLINE).unwrap();
+    assert_eq!(game.end(), Some(Some(Player::White)));
+
+    // The same double three, laid by white.
+    let mut game = GameState::with_rules(19, rules);
+    play_all(&mut game, &[(0, 0), (5, 7), (0, 18), (6, 7), (18, 0), (7, 5), (18, 18), (7, 6), (9, 18)]);
+    assert_eq!(game.player_turn(), Player::White);
+    assert!(game.is_legal(DOUBLE_THREE));
+}
+
+/// The home menu builds its rules from a kind, an opening and capture rules, and the game it
+/// starts hands them to the solver.
+#[test]
+fn menu_rules_reach_the_solver() {
+    let menu_rules = |kind: RuleKind| {
+        let base = RuleSet::new(kind);
+        base.with_opening(Opening::Free).with_capture(base.capture)
+    };
+    let config = SolverConfig { time_limit: Some(Duration::from_millis(500)), threads: 1, ..SolverConfig::default() };
+
+    let game = double_three(menu_rules(RuleKind::Freestyle));
+    assert_eq!(Solver::new(config).search(&game).best_move, DOUBLE_THREE);
+    for &kind in [RuleKind::Renju, RuleKind::FortyTwo].iter() {
+        let game = double_three(menu_rules(kind));
+        let res = Solver::new(config).search(&game);
+        assert_ne!(res.best_move, DOUBLE_THREE, "{}", kind.name());
+        assert!(game.is_legal(res.best_move), "{}", kind.name());
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use gomoku_rs::solver::{nega_max, MoveOrder, SearchCtx, SearchResult, Solver, SolverConfig, INF};
+use gomoku_rs::tt::TranspositionTable;
+use gomoku_rs::vcf::{find_vcf, Limits, MAX_DEPTH_VCF};
+use gomoku_rs::{GameState, Player, Pos, RuleKind, RuleSet};
+
+mod common;
+use common::{play_all, two_threes};
+
+/// Replays the line of `res`, which starts with its move and runs at least as deep as the
+/// search went, unless the game ends on the way.
+fn assert_pv_replays(game: &GameState, res: &SearchResult) {
+    assert_eq!(res.pv.first(), Some(&res.best_move));
+    let mut game = game.clone();
+    for p in res.pv.iter() {
+        assert!(game.end().is_none(), "pv {:?} goes on after the end", res.pv);
+        game.play(*p).unwrap();
+    }
+    assert!(game.end().is_some() || res.pv.len() >= res.depth as usize, "pv {:?} at depth {}", res.pv, res.depth);
+}
+
+#[test]
+fn pv_is_a_legal_line_as_deep_as_the_search() {
+    for &kind in [RuleKind::Freestyle, RuleKind::Renju, RuleKind::FortyTwo, RuleKind::Caro].iter() {
+        let mut game = GameState::with_rules(19, RuleSet::new(kind));
+        play_all(&mut game, &[(9, 9), (10, 10), (9, 10), (8, 8), (11, 9)]);
+
+        let solver = Solver::new(SolverConfig { time_limit: None, max_depth: 4, threads: 1, ..SolverConfig::default() });
+        let res = solver.search(&game);
+        assert_eq!(res.depth, 4, "{}", kind.name());
+        assert_pv_replays(&game, &res);
+        // Again, with the table full of the first search.
+        assert_pv_replays(&game, &solver.search(&game));
+
+        // Two moves later, as in a game.
+        for p in res.pv.iter().take(2) {
+            game.play(*p).unwrap();
+        }
+        assert_pv_replays(&game, &solver.search(&game));
+    }
+}
+
+fn horizon_search(game: &GameState, depth: i16) -> (i32, Vec<Pos>) {
+    let ctx = SearchCtx {
+        tt: Arc::new(TranspositionTable::new(1)),
+        stop: Arc::new(AtomicBool::new(false)),
+        deadline: None,
+        root_depth: depth,
+        rules: *game.rules(),
+    };
+    let mut pv = Vec::new();
+    let (_, score) = nega_max(
+        game.board(),
+        game.nb_cap(Player::White),
+        game.nb_cap(Player::Black),
+        depth,
+        0,
+        -INF,
+        INF,
+        game.player_turn(),
+        game.last_move(),
+        game.prev_move(),
+        &ctx,
+        &mut MoveOrder::new(),
+        &mut pv,
+    );
+    (score, pv)
+}
+
+/// The forced wins by fours are left to quiescence past the horizon: it finds the one of
+/// `two_threes` from a search with no depth left, down to the five.
+#[test]
+fn quiescence_finds_the_forced_win_by_fours() {
+    for &kind in [RuleKind::Freestyle, RuleKind::Standard, RuleKind::Renju, RuleKind::Pente, RuleKind::FortyTwo].iter() {
+        let mut game = two_threes(RuleSet::new(kind));
+        assert!(find_vcf(&game, 4, Limits::vcf()).is_some(), "{}", kind.name());
+
+        let (score, pv) = horizon_search(&game, 0);
+        assert!(score > INF / 4, "{}: score {}", kind.name(), score);
+        for p in pv.iter() {
+            game.play(*p).unwrap();
+        }
+        assert_eq!(game.end(), Some(Some(Player::Black)), "{}", kind.name());
+    }
+}
+
+#[test]
+fn quiescence_finds_no_win_without_fours() {
+    let mut game = GameState::with_rules(19, RuleSet::new(RuleKind::Freestyle));
+    play_all(&mut game, &[(9, 9), (10, 10), (9, 10)]);
+    assert_eq!(find_vcf(&game, MAX_DEPTH_VCF, Limits::vcf()), None);
+
+    let (score, _) = horizon_search(&game, 0);
+    assert!(score.abs() < INF / 4, "score {}", score);
+}
+
+#[test]
+fn one_thread_search_is_deterministic() {
+    let mut game = GameState::with_rules(19, RuleSet::new(RuleKind::FortyTwo));
+    play_all(&mut game, &[(9, 9), (10, 10), (9, 10), (8, 8), (11, 9), (9, 11)]);
+
+    let config = SolverConfig { time_limit: None, max_depth: 4, threads: 1, ..SolverConfig::default() };
+    let first = Solver::new(config).search(&game);
+    for _ in 0..3 {
+        let res = Solver::new(config).search(&game);
+        assert_eq!((res.best_move, res.score, res.depth, &res.pv), (first.best_move, first.score, first.depth, &first.pv));
+    }
+}
+
+#[test]
+fn stop_set_before_the_search_ends_it_at_once() {
+    let mut game = GameState::with_rules(19, RuleSet::new(RuleKind::Freestyle));
+    play_all(&mut game, &[(9, 9), (10, 10), (9, 10), (8, 8)]);
+
+    let mut solver = Solver::new(SolverConfig { time_limit: None, max_depth: 40, threads: 2, ..SolverConfig::default() });
+    let stop = Arc::new(AtomicBool::new(true));
+    solver.set_stop_handle(Arc::clone(&stop));
+    let res = solver.search(&game);
+    assert_eq!(res.depth, 0);
+    assert!(game.is_legal(res.best_move));
+    assert!(!stop.load(Ordering::Relaxed));
+}
+
+#[test]
+fn search_returns_within_the_time_limit() {
+    let limit = Duration::from_millis(20);
+    for &kind in [RuleKind::Freestyle, RuleKind::Renju, RuleKind::FortyTwo].iter() {
+        let mut game = GameState::with_rules(19, RuleSet::new(kind));
+        play_all(&mut game, &[(9, 9), (10, 10), (9, 10), (8, 8), (11, 9), (9, 11), (10, 9), (8, 9), (12, 9)]);
+        let solver = Solver::new(SolverConfig { time_limit: Some(limit), threads: 2, ..SolverConfig::default() });
+        for _ in 0..3 {
+            let start = Instant::now();
+            let res = solver.search(&game);
+            assert!(start.elapsed() < limit + Duration::from_millis(30), "{}: {:?}", kind.name(), start.elapsed());
+            game.play(res.best_move).unwrap();
+            if game.end().is_some() {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn connect6_search_completes_six_with_both_stones() {
+    let mut game = GameState::with_rules(19, RuleSet::new(RuleKind::Connect6));
+    play_all(&mut game, &[(5, 9), (0, 0), (0, 2), (6, 9), (7, 9), (0, 4), (0, 6), (8, 9), (14, 14), (0, 10), (2, 12)]);
+    assert_eq!(game.player_turn(), Player::Black);
+
+    let solver = Solver::new(SolverConfig { time_limit: None, max_depth: 3, threads: 1, ..SolverConfig::default() });
+    let res = solver.search(&game);
+    assert!(res.score > INF / 4, "score {}", res.score);
+    assert_eq!(res.pv.first(), Some(&res.best_move));
+    for p in res.pv.iter().take(2) {
+        assert_eq!(game.player_turn(), Player::Black);
+        game.play(*p).unwrap();
+    }
+    assert_eq!(game.end(), Some(Some(Player::Black)));
+}
@@ -0,0 +1,34 @@
+use gomoku_rs::solver::INF;
+use gomoku_rs::tt::{Bound, TTEntry, TranspositionTable};
+use gomoku_rs::Pos;
+
+#[test]
+fn entries_come_back_as_stored() {
+    let tt = TranspositionTable::new(1);
+    let moves = [None, Some(Pos { x: 0, y: 0 }), Some(Pos { x: 9, y: 9 }), Some(Pos { x: 19, y: 19 })];
+    let mut key: u64 = 1;
+    for &depth in [-8, -1, 0, 1, 20, 127].iter() {
+        for &score in [-INF, -1_000_000, -1, 0, 1, 1_000_000, INF].iter() {
+            for &bound in [Bound::Exact, Bound::Lower, Bound::Upper].iter() {
+                for &best_move in moves.iter() {
+                    let entry = TTEntry { depth, bound, score, best_move };
+                    key = key.wrapping_mul(0x9e37_79b9_7f4a_7c15).wrapping_add(1);
+                    tt.store(key, entry);
+                    assert_eq!(tt.probe(key), Some(entry));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn key_not_stored_is_a_miss() {
+    let tt = TranspositionTable::new(1);
+    let entry = TTEntry { depth: 3, bound: Bound::Exact, score: 42, best_move: Some(Pos { x: 4, y: 5 }) };
+    tt.store(7, entry);
+    assert_eq!(tt.probe(7), Some(entry));
+    assert_eq!(tt.probe(8), None);
+
+    tt.clear();
+    assert_eq!(tt.probe(7), None);
+}
@@ -0,0 +1,131 @@
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+use gomoku_rs::solver::{Solver, SolverConfig};
+use gomoku_rs::vcf::{find_vcf, find_vct, Limits, MAX_DEPTH_VCF, MAX_DEPTH_VCT};
+use gomoku_rs::{GameState, Player, Pos, RuleKind, RuleSet};
+
+mod common;
+use common::{play_all, two_threes};
+
+#[test]
+fn vcf_line_wins_when_replayed() {
+    let mut game = two_threes(RuleSet::new(RuleKind::Freestyle));
+    let line = find_vcf(&game, MAX_DEPTH_VCF, Limits::vcf()).expect("black wins by fours");
+
+    for p in line.iter() {
+        game.play(*p).unwrap();
+    }
+    if game.end().is_none() {
+        // The line ended with a double four: any reply, then the open five point, wins.
+        let reply = game.legal_moves()[0];
+        game.play(reply).unwrap();
+        let win = find_vcf(&game, 0, Limits::vcf()).expect("a five to complete");
+        game.play(win[0]).unwrap();
+    }
+    assert_eq!(game.end(), Some(Some(Player::Black)));
+}
+
+#[test]
+fn no_vcf_without_fours() {
+    let mut game = GameState::with_rules(19, RuleSet::new(RuleKind::Freestyle));
+    play_all(&mut game, &[(9, 9), (10, 10), (9, 10)]);
+    assert_eq!(find_vcf(&game, MAX_DEPTH_VCF, Limits::vcf()), None);
+}
+
+/// Black, to move, has a row two and a column two meeting on (9, 9): a double three there.
+fn two_twos() -> GameState {
+    let mut game = GameState::with_rules(19, RuleSet::new(RuleKind::Freestyle));
+    play_all(&mut game, &[(7, 9), (0, 0), (8, 9), (0, 18), (9, 7), (18, 0), (9, 6), (18, 18)]);
+    game
+}
+
+#[test]
+fn vct_double_three_beats_every_reply() {
+    let mut game = two_twos();
+    assert_eq!(find_vcf(&game, MAX_DEPTH_VCF, Limits::vcf()), None);
+    let line = find_vct(&game, Player::Black, MAX_DEPTH_VCT, Limits::vct()).expect("black wins by threats");
+    assert_eq!(line[0], Pos { x: 9, y: 9 });
+
+    game.play(line[0]).unwrap();
+    for r in game.legal_moves() {
+        let mut cp = game.clone();
+        cp.play(r).unwrap();
+        assert!(find_vct(&cp, Player::Black, MAX_DEPTH_VCT, Limits::vct()).is_some(), "white escapes with {:?}", r);
+    }
+}
+
+#[test]
+fn solver_stops_a_vct() {
+    let mut game = two_twos();
+    game.undo();
+    assert!(find_vct(&game, Player::Black, MAX_DEPTH_VCT, Limits::vct()).is_some());
+
+    let solver = Solver::new(SolverConfig {
+        time_limit: Some(Duration::from_millis(4000)),
+        max_depth: 4,
+        threads: 1,
+        ..SolverConfig::default()
+    });
+    game.play(solver.best_move(&game)).unwrap();
+    assert_eq!(find_vct(&game, Player::Black, MAX_DEPTH_VCT, Limits::vct()), None);
+}
+
+#[test]
+fn threat_search_ends_on_the_stop_flag_and_the_deadline() {
+    let game = two_twos();
+    let (go, stop) = (AtomicBool::new(false), AtomicBool::new(true));
+    let limits = Limits::vct().until(&go, Some(Instant::now() + Duration::from_secs(60)));
+    assert!(find_vct(&game, Player::Black, MAX_DEPTH_VCT, limits).is_some());
+
+    assert_eq!(find_vct(&game, Player::Black, MAX_DEPTH_VCT, Limits::vct().until(&stop, None)), None);
+    assert_eq!(find_vct(&game, Player::Black, MAX_DEPTH_VCT, Limits::vct().until(&go, Some(Instant::now()))), None);
+}
+
+#[test]
+fn solver_plays_a_forced_win_at_no_depth() {
+    let game = two_threes(RuleSet::new(RuleKind::Freestyle));
+    let line = find_vcf(&game, MAX_DEPTH_VCF, Limits::vcf()).unwrap();
+
+    let res = Solver::new(SolverConfig { threads: 1, ..SolverConfig::default() }).search(&game);
+    assert_eq!(res.depth, 0);
+    assert_eq!(res.best_move, line[0]);
+    assert_eq!(res.pv, line);
+}
+
+/// The solver's own pick leaves black its threats, the defence played instead comes with the
+/// line and score of a search of its own.
+#[test]
+fn defence_comes_with_its_own_line() {
+    let mut game = two_twos();
+    game.undo();
+
+    let solver = Solver::new(SolverConfig { time_limit: None, max_depth: 4, threads: 1, ..SolverConfig::default() });
+    let res = solver.search(&game);
+    assert_eq!(res.best_move, Pos { x: 9, y: 9 });
+    assert_eq!(res.pv.first(), Some(&res.best_move));
+    assert_eq!(res.depth, 4);
+    assert!(res.pv.len() >= res.depth as usize, "pv {:?}", res.pv);
+    for p in res.pv.iter() {
+        game.play(*p).unwrap();
+    }
+}
+
+/// Black has a five white can only break at (7, 7): the fours white would win by come too late.
+#[test]
+fn five_to_break_comes_before_a_forced_win() {
+    let mut game = GameState::with_rules(19, RuleSet::new(RuleKind::FortyTwo));
+    play_all(&mut game, &[(5, 5), (7, 4), (6, 5), (0, 0), (7, 6), (0, 2), (8, 5), (0, 4), (9, 5), (0, 6), (7, 5)]);
+    let breaks = game.pending_five().map(|f| f.breaks.clone());
+    assert_eq!(breaks, Some(vec![Pos { x: 7, y: 7 }]));
+    assert_eq!(find_vcf(&game, MAX_DEPTH_VCF, Limits::vcf()), None);
+    assert_eq!(find_vct(&game, Player::White, MAX_DEPTH_VCT, Limits::vct()), None);
+
+    for &threads in [1, 4].iter() {
+        let solver = Solver::new(SolverConfig { time_limit: Some(Duration::from_millis(500)), threads, ..SolverConfig::default() });
+        let res = solver.search(&game);
+        assert_eq!(res.best_move, Pos { x: 7, y: 7 }, "{} threads", threads);
+        let mut cp = game.clone();
+        cp.play(res.best_move).unwrap();
+        assert_eq!(cp.end(), None);
+    }
+}
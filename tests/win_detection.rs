@@ -0,0 +1,170 @@
+use gomoku_rs::board::*;
+use gomoku_rs::{GameState, Player, Pos, RuleKind, RuleSet};
+
+mod common;
+use common::{play_all, random_move, Rng};
+
+fn full_scan(game: &GameState) -> Option<Player> {
+    check_end_grd(
+        game.board(),
+        game.nb_cap(Player::White),
+        game.nb_cap(Player::Black),
+        game.player_turn(),
+        None,
+        game.rules(),
+    )
+}
+
+fn local_scan(game: &GameState) -> Option<Player> {
+    check_end_local(
+        game.board(),
+        game.nb_cap(Player::White),
+        game.nb_cap(Player::Black),
+        game.player_turn(),
+        game.last_move().unwrap(),
+        game.prev_move(),
+        game.rules(),
+    )
+}
+
+#[test]
+fn local_scan_matches_full_scan_on_random_games() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    let mut nb_five = 0;
+
+    for game_id in 0..90 {
+        let size = [9, 11, 15][game_id % 3];
+        let mut game = GameState::with_size(size);
+
+        while game.end().is_none() && !game.legal_moves().is_empty() {
+            let p = random_move(&game, &mut rng);
+            game.play(p).unwrap();
+
+            if game.nb_cap(Player::White) >= NB_CAP_WIN || game.nb_cap(Player::Black) >= NB_CAP_WIN {
+                break;
+            }
+            let full = full_scan(&game);
+            assert_eq!(full, local_scan(&game), "game {} move {:?}", game_id, p);
+            assert_eq!(full.map(Some), game.end());
+            if check_align_5p(game.board(), player_to_i8(next_player(game.player_turn()))) {
+                nb_five += 1;
+            }
+        }
+    }
+
+    assert!(nb_five > 30);
+}
+
+#[test]
+fn unbreakable_five_wins() {
+    let mut game = GameState::new();
+    play_all(&mut game, &[(5, 5), (0, 0), (6, 5), (0, 2), (7, 5), (0, 4), (8, 5), (0, 6), (9, 5)]);
+
+    assert_eq!(local_scan(&game), Some(Player::Black));
+    assert_eq!(game.end(), Some(Some(Player::Black)));
+}
+
+#[test]
+fn breakable_five_is_pending() {
+    let mut game = GameState::new();
+    // White can capture the black pair (7,5) (7,6) from (7,7) thanks to (7,4).
+    play_all(&mut game, &[(5, 5), (7, 4), (6, 5), (0, 0), (7, 6), (0, 2), (8, 5), (0, 4), (9, 5), (0, 6), (7, 5)]);
+
+    assert_eq!(full_scan(&game), None);
+    assert_eq!(local_scan(&game), None);
+    assert_eq!(game.end(), None);
+
+    game.play(Pos { x: 7, y: 7 }).unwrap();
+    assert_eq!(game.nb_cap(Player::White), 2);
+    assert_eq!(game.end(), None);
+}
+
+#[test]
+fn pending_five_wins_when_not_broken() {
+    let mut game = GameState::new();
+    play_all(&mut game, &[(5, 5), (7, 4), (6, 5), (0, 0), (7, 6), (0, 2), (8, 5), (0, 4), (9, 5), (0, 6), (7, 5)]);
+
+    game.play(Pos { x: 18, y: 18 }).unwrap();
+    assert_eq!(full_scan(&game), Some(Player::Black));
+    assert_eq!(local_scan(&game), Some(Player::Black));
+    assert_eq!(game.end(), Some(Some(Player::Black)));
+}
+
+#[test]
+fn six_stays_five_when_an_end_is_captured() {
+    let mut board = Board::new(19);
+    for x in 3..9 {
+        board.set(Pos { x, y: 5 }, CELL_BLACK);
+    }
+    // (8,5) (8,6) can be captured by white from (8,7) thanks to (8,4).
+    board.set(Pos { x: 8, y: 6 }, CELL_BLACK);
+    board.set(Pos { x: 8, y: 4 }, CELL_WHITE);
+
+    let last_move = Pos { x: 8, y: 5 };
+    assert_eq!(check_end_local(&board, 0, 0, Player::White, last_move, None, &RuleSet::default()), Some(Player::Black));
+    assert_eq!(check_end_grd(&board, 0, 0, Player::White, Some(last_move), &RuleSet::default()), Some(Player::Black));
+}
+
+#[test]
+fn capture_reaching_the_limit_breaks_the_five() {
+    let mut board = Board::new(19);
+    for x in 3..8 {
+        board.set(Pos { x, y: 5 }, CELL_BLACK);
+    }
+    board.set(Pos { x: 12, y: 12 }, CELL_BLACK);
+    board.set(Pos { x: 13, y: 12 }, CELL_BLACK);
+    board.set(Pos { x: 14, y: 12 }, CELL_WHITE);
+
+    let last_move = Pos { x: 7, y: 5 };
+    assert_eq!(check_end_local(&board, 8, 0, Player::White, last_move, None, &RuleSet::default()), None);
+    assert_eq!(check_end_local(&board, 6, 0, Player::White, last_move, None, &RuleSet::default()), Some(Player::Black));
+    assert_eq!(check_end_grd(&board, 8, 0, Player::White, Some(last_move), &RuleSet::default()), None);
+}
+
+#[test]
+fn caro_five_blocked_at_both_ends_does_not_win() {
+    let caro = RuleSet::new(RuleKind::Caro);
+    let mut game = GameState::with_rules(19, caro);
+    play_all(&mut game, &[(4, 9), (3, 9), (5, 9), (9, 9), (6, 9), (0, 0), (7, 9), (0, 2), (8, 9)]);
+    assert_eq!(game.end(), None);
+    assert_eq!(full_scan(&game), None);
+
+    let mut game = GameState::with_rules(19, caro);
+    play_all(&mut game, &[(4, 9), (3, 9), (5, 9), (18, 18), (6, 9), (0, 0), (7, 9), (0, 2), (8, 9)]);
+    assert_eq!(game.end(), Some(Some(Player::Black)));
+    assert_eq!(full_scan(&game), Some(Player::Black));
+}
+
+#[test]
+fn pending_five_lists_its_stones_and_breaks() {
+    let mut game = GameState::new();
+    play_all(&mut game, &[(5, 5), (7, 4), (6, 5), (0, 0), (7, 6), (0, 2), (8, 5), (0, 4), (9, 5), (0, 6), (7, 5)]);
+
+    let five = game.pending_five().unwrap();
+    assert_eq!(five.player, Player::Black);
+    let mut stones = five.stones.clone();
+    stones.sort_by_key(|p| p.x);
+    assert_eq!(stones, (5..10).map(|x| Pos { x, y: 5 }).collect::<Vec<_>>());
+    assert_eq!(five.breaks, vec![Pos { x: 7, y: 7 }]);
+}
+
+#[test]
+fn caro_five_stones_leave_out_a_blocked_five() {
+    let mut board = Board::new(19);
+    for k in 4..9 {
+        board.set(Pos { x: k, y: 9 }, CELL_BLACK);
+        board.set(Pos { x: 6, y: k + 1 }, CELL_BLACK);
+    }
+    board.set(Pos { x: 3, y: 9 }, CELL_WHITE);
+    board.set(Pos { x: 9, y: 9 }, CELL_WHITE);
+    let p = Pos { x: 6, y: 9 };
+
+    let mut stones = RuleSet::new(RuleKind::Caro).five_stones_at(&board, p, Player::Black);
+    stones.sort_by_key(|p| p.y);
+    assert_eq!(stones, (5..10).map(|y| Pos { x: 6, y }).collect::<Vec<_>>());
+    assert_eq!(RuleSet::new(RuleKind::Freestyle).five_stones_at(&board, p, Player::Black).len(), 9);
+
+    board.set(Pos { x: 6, y: 4 }, CELL_WHITE);
+    board.set(Pos { x: 6, y: 10 }, CELL_WHITE);
+    assert!(RuleSet::new(RuleKind::Caro).five_stones_at(&board, p, Player::Black).is_empty());
+}
@@ -0,0 +1,63 @@
+use gomoku_rs::board::{CELL_BLACK, CELL_WHITE};
+use gomoku_rs::zobrist::{cap_key, position_key, stone_key, turn_key};
+use gomoku_rs::{GameState, Player, RuleKind, RuleSet};
+
+mod common;
+use common::{random_move, Rng};
+
+/// Key of the position of `game`, from the stones on the board.
+fn scratch_key(game: &GameState) -> u64 {
+    let mut key = cap_key(Player::White, game.nb_cap(Player::White))
+        ^ cap_key(Player::Black, game.nb_cap(Player::Black))
+        ^ turn_key(game.player_turn());
+    for &c in [CELL_BLACK, CELL_WHITE].iter() {
+        for i in game.board().stones(c).iter() {
+            key ^= stone_key(i, c);
+        }
+    }
+    key
+}
+
+fn key(game: &GameState) -> u64 {
+    position_key(game.board(), game.nb_cap(Player::White), game.nb_cap(Player::Black), game.player_turn())
+}
+
+#[test]
+fn incremental_key_matches_the_key_from_scratch() {
+    let mut rng = Rng(0x243f_6a88_85a3_08d3);
+    let mut nb_capture = 0;
+
+    for game_id in 0..40 {
+        let size = [9, 11, 15, 19][game_id % 4];
+        let mut game = GameState::with_rules(size, RuleSet::new(RuleKind::FortyTwo));
+        while game.end().is_none() && !game.legal_moves().is_empty() {
+            let outcome = game.play(random_move(&game, &mut rng)).unwrap();
+            nb_capture += (!outcome.captured.is_empty()) as usize;
+            assert_eq!(key(&game), scratch_key(&game));
+
+            // Back a few moves, captures included, and forth again.
+            if rng.gen_range(0, 4) == 0 {
+                let nb_undo = rng.gen_range(1, 4);
+                for _ in 0..nb_undo {
+                    if game.undo().is_some() {
+                        assert_eq!(key(&game), scratch_key(&game));
+                    }
+                }
+                while game.redo().is_some() {
+                    assert_eq!(key(&game), scratch_key(&game));
+                }
+            }
+        }
+    }
+
+    assert!(nb_capture > 20, "{} captures", nb_capture);
+}
+
+#[test]
+fn key_tells_the_player_to_move_and_the_captures_apart() {
+    let game = GameState::new();
+    let grd = game.board();
+    assert_ne!(position_key(grd, 0, 0, Player::Black), position_key(grd, 0, 0, Player::White));
+    assert_ne!(position_key(grd, 2, 0, Player::Black), position_key(grd, 0, 2, Player::Black));
+    assert_ne!(position_key(grd, 2, 0, Player::Black), position_key(grd, 4, 0, Player::Black));
+}